@@ -0,0 +1,32 @@
+//! Grid-cell map micro-benchmark: default SipHash vs FxHash
+//!
+//! Run with `cargo bench --bench hashing`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rustc_hash::FxHashMap;
+use std::collections::HashMap;
+
+const CELLS: i32 = 128;
+
+fn cells() -> impl Iterator<Item = (i32, i32)> {
+    (0..CELLS).flat_map(|cx| (0..CELLS).map(move |cy| (cx, cy)))
+}
+
+fn bench_cell_lookup(c: &mut Criterion) {
+    let mut group = c.benchmark_group("cell_lookup");
+
+    let sip: HashMap<(i32, i32), Vec<u32>> = cells().map(|cell| (cell, vec![0])).collect();
+    group.bench_function("siphash", |b| {
+        b.iter(|| cells().filter(|cell| sip.contains_key(black_box(cell))).count())
+    });
+
+    let fx: FxHashMap<(i32, i32), Vec<u32>> = cells().map(|cell| (cell, vec![0])).collect();
+    group.bench_function("fxhash", |b| {
+        b.iter(|| cells().filter(|cell| fx.contains_key(black_box(cell))).count())
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_cell_lookup);
+criterion_main!(benches);
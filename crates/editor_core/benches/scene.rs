@@ -0,0 +1,150 @@
+//! Spatial index throughput on a 50k-node scene
+//!
+//! Compare hashers or other changes with criterion baselines:
+//! `cargo bench --bench scene -- --save-baseline before`, apply the change,
+//! then `cargo bench --bench scene -- --baseline before`.
+//! The 200k-node wide query is the one to compare with `--features parallel`.
+//! The dense cluster bench exercises point queries into sub-divided hot cells.
+//! The guide benches compare a full per-frame alignment pass with a cached session.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use editor_core::{EditorCore, SpatialIndex};
+
+const NODE_COUNT: u32 = 50_000;
+const LARGE_NODE_COUNT: u32 = 200_000;
+const COLUMNS: u32 = 224;
+const LARGE_COLUMNS: u32 = 448;
+const CLUSTER_NODE_COUNT: u32 = 20_000;
+
+/// 40x30 nodes on a lattice with the given column count (60x50 pitch)
+fn node_bounds(handle: u32, columns: u32) -> (f32, f32, f32, f32) {
+    let x = (handle % columns) as f32 * 60.0;
+    let y = (handle / columns) as f32 * 50.0;
+    (x, y, x + 40.0, y + 30.0)
+}
+
+fn build_index_of(count: u32, columns: u32) -> SpatialIndex {
+    let mut index = SpatialIndex::new(count as usize);
+    for handle in 0..count {
+        let (min_x, min_y, max_x, max_y) = node_bounds(handle, columns);
+        index.upsert(handle, min_x, min_y, max_x, max_y, 0.0);
+    }
+    index
+}
+
+fn build_index() -> SpatialIndex {
+    build_index_of(NODE_COUNT, COLUMNS)
+}
+
+fn bench_upsert(c: &mut Criterion) {
+    c.bench_function("upsert_50k", |b| b.iter(|| black_box(build_index())));
+}
+
+fn bench_drag(c: &mut Criterion) {
+    let mut index = build_index();
+    let handle = COLUMNS * 10 + 10;
+    let (min_x, min_y, max_x, max_y) = node_bounds(handle, COLUMNS);
+
+    // 1px steps back and forth: most frames stay within the same cells
+    c.bench_function("upsert_drag_1px", |b| {
+        let mut step = 0u32;
+        b.iter(|| {
+            step = (step + 1) % 32;
+            let dx = step as f32;
+            index.upsert(black_box(handle), min_x + dx, min_y, max_x + dx, max_y, 0.0);
+        })
+    });
+}
+
+fn bench_query_rect(c: &mut Criterion) {
+    let index = build_index();
+    c.bench_function("query_rect_50k", |b| {
+        b.iter(|| index.query_rect(black_box(2000.0), black_box(2000.0), 3920.0, 3080.0).len())
+    });
+
+    // Large marquee selection: most cells lie wholly inside the rect
+    c.bench_function("query_rect_50k_marquee", |b| {
+        b.iter(|| index.query_rect(black_box(100.0), black_box(100.0), 9000.0, 8000.0).len())
+    });
+}
+
+fn bench_cull_visible(c: &mut Criterion) {
+    let mut core = EditorCore::new(NODE_COUNT as usize);
+    for handle in 0..NODE_COUNT {
+        let (min_x, min_y, max_x, max_y) = node_bounds(handle, COLUMNS);
+        core.upsert_node(handle, min_x, min_y, max_x, max_y, 0.0, 0);
+    }
+    core.set_camera(1.0, 3000.0, 2500.0, 1920.0, 1080.0, 1.0, None, None, None, None);
+
+    c.bench_function("cull_visible_50k", |b| b.iter(|| core.cull_visible().len()));
+
+    // Zoomed out so the whole scene is on screen: the narrow phase dominates
+    core.set_camera(0.1, 6720.0, 5600.0, 1920.0, 1080.0, 1.0, None, None, None, None);
+    c.bench_function("cull_dense_50k", |b| b.iter(|| core.cull_visible().len()));
+}
+
+fn bench_large_query(c: &mut Criterion) {
+    let index = build_index_of(LARGE_NODE_COUNT, LARGE_COLUMNS);
+
+    // 60x60 cells: wide enough for the parallel path, below the linear-scan cutoff
+    c.bench_function("query_rect_200k_wide", |b| {
+        b.iter(|| index.query_rect(black_box(0.0), black_box(0.0), 15_359.0, 15_359.0).len())
+    });
+}
+
+fn bench_dense_cluster(c: &mut Criterion) {
+    // 3x3 dots on a 4px pitch: 20k nodes packed into a 2x3 block of 256px cells
+    let mut index = SpatialIndex::new(CLUSTER_NODE_COUNT as usize);
+    for handle in 0..CLUSTER_NODE_COUNT {
+        let x = (handle % 128) as f32 * 4.0;
+        let y = (handle / 128) as f32 * 4.0;
+        index.upsert(handle, x, y, x + 3.0, y + 3.0, 0.0);
+    }
+
+    c.bench_function("query_point_dense_cluster", |b| {
+        let mut i = 0u32;
+        b.iter(|| {
+            i = i.wrapping_add(7919);
+            let x = (i % 512) as f32 + 0.5;
+            let y = (i / 512 % 600) as f32 + 0.5;
+            index.query_point(black_box(x), black_box(y)).len()
+        })
+    });
+}
+
+fn bench_alignment_guides(c: &mut Criterion) {
+    let mut core = EditorCore::new(NODE_COUNT as usize);
+    for handle in 0..NODE_COUNT {
+        let (min_x, min_y, max_x, max_y) = node_bounds(handle, COLUMNS);
+        core.upsert_node(handle, min_x, min_y, max_x, max_y, 0.0, 0);
+    }
+    let visible: Vec<u32> = (0..10_000).collect();
+    let moving = 5_000;
+
+    c.bench_function("alignment_guides_10k_visible", |b| {
+        b.iter(|| core.calculate_alignment_guides(moving, black_box(visible.clone()), 1.0, None, None).len())
+    });
+
+    core.begin_guide_session(moving, visible.clone(), 1.0, None);
+    c.bench_function("guide_session_update_10k_visible", |b| {
+        let mut step = 0u32;
+        b.iter(|| {
+            step = step.wrapping_add(1);
+            let x = 1000.0 + (step % 100) as f32 * 0.5;
+            core.update_guide_session(black_box(x), 1000.0, x + 40.0, 1030.0).len()
+        })
+    });
+    core.end_guide_session();
+}
+
+criterion_group!(
+    benches,
+    bench_upsert,
+    bench_drag,
+    bench_query_rect,
+    bench_cull_visible,
+    bench_large_query,
+    bench_dense_cluster,
+    bench_alignment_guides
+);
+criterion_main!(benches);
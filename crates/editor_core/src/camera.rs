@@ -1,5 +1,8 @@
 //! Camera transform system for screen <-> world coordinate conversion
 
+const DEFAULT_MIN_ZOOM: f32 = 0.01;
+const DEFAULT_MAX_ZOOM: f32 = 256.0;
+
 pub struct Camera {
     zoom: f32,
     pan_x: f32,
@@ -7,6 +10,18 @@ pub struct Camera {
     viewport_w: f32,
     viewport_h: f32,
     dpr: f32,
+    origin_x: Option<f32>, // screen position of the pan point; None = viewport center
+    origin_y: Option<f32>,
+    flip_x: bool, // world x grows leftwards on screen (e.g. RTL)
+    flip_y: bool, // world y grows upwards on screen (Y-up data)
+    min_zoom: f32,
+    max_zoom: f32,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Camera {
@@ -18,9 +33,19 @@ impl Camera {
             viewport_w: 800.0,
             viewport_h: 600.0,
             dpr: 1.0,
+            origin_x: None,
+            origin_y: None,
+            flip_x: false,
+            flip_y: false,
+            min_zoom: DEFAULT_MIN_ZOOM,
+            max_zoom: DEFAULT_MAX_ZOOM,
         }
     }
 
+    /// Set the transform; `origin_x`/`origin_y` place the pan point at a pixel
+    /// offset within the viewport (e.g. right of a sidebar), defaulting to centered.
+    /// `flip_x`/`flip_y` mirror an axis (e.g. Y-up world data), defaulting to false.
+    #[allow(clippy::too_many_arguments)]
     pub fn set(
         &mut self,
         zoom: f32,
@@ -29,27 +54,115 @@ impl Camera {
         viewport_w: f32,
         viewport_h: f32,
         dpr: f32,
+        origin_x: Option<f32>,
+        origin_y: Option<f32>,
+        flip_x: Option<bool>,
+        flip_y: Option<bool>,
     ) {
-        self.zoom = zoom;
+        // Non-positive or non-finite zoom would make the transforms produce NaN
+        if zoom > 0.0 && zoom.is_finite() {
+            self.zoom = zoom.clamp(self.min_zoom, self.max_zoom);
+        }
         self.pan_x = pan_x;
         self.pan_y = pan_y;
         self.viewport_w = viewport_w;
         self.viewport_h = viewport_h;
         self.dpr = dpr;
+        self.origin_x = origin_x;
+        self.origin_y = origin_y;
+        self.flip_x = flip_x.unwrap_or(false);
+        self.flip_y = flip_y.unwrap_or(false);
+    }
+
+    /// Set the allowed zoom range, ignoring invalid ranges
+    pub fn set_zoom_limits(&mut self, min: f32, max: f32) {
+        if min > 0.0 && min <= max {
+            self.min_zoom = min;
+            self.max_zoom = max;
+            self.zoom = self.zoom.clamp(min, max);
+        }
+    }
+
+    /// Get the current zoom factor
+    pub fn zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    /// Get the world x coordinate at the viewport origin
+    pub fn pan_x(&self) -> f32 {
+        self.pan_x
+    }
+
+    /// Get the world y coordinate at the viewport origin
+    pub fn pan_y(&self) -> f32 {
+        self.pan_y
+    }
+
+    /// Get the device pixel ratio
+    pub fn dpr(&self) -> f32 {
+        self.dpr
+    }
+
+    /// Get all transform parameters as passed to `set`
+    /// (zoom, pan_x, pan_y, viewport_w, viewport_h, dpr)
+    pub fn params(&self) -> (f32, f32, f32, f32, f32, f32) {
+        (self.zoom, self.pan_x, self.pan_y, self.viewport_w, self.viewport_h, self.dpr)
+    }
+
+    /// Get the screen position the pan point maps to (viewport center unless set)
+    pub fn origin(&self) -> (f32, f32) {
+        (
+            self.origin_x.unwrap_or(self.viewport_w / 2.0),
+            self.origin_y.unwrap_or(self.viewport_h / 2.0),
+        )
+    }
+
+    /// Get the signed per-axis scale from world to screen (negative on flipped axes)
+    fn axis_scale(&self) -> (f32, f32) {
+        let scale_x = if self.flip_x { -self.zoom } else { self.zoom };
+        let scale_y = if self.flip_y { -self.zoom } else { self.zoom };
+        (scale_x, scale_y)
+    }
+
+    /// Get how many world units map to one device pixel at the current zoom/dpr
+    pub fn world_per_pixel(&self) -> f32 {
+        1.0 / (self.zoom * self.dpr)
+    }
+
+    /// Convert a world length to a screen length in CSS pixels (the unit of
+    /// `world_to_screen`); multiply by dpr for device pixels
+    pub fn world_length_to_screen(&self, len: f32) -> f32 {
+        len * self.zoom
+    }
+
+    /// Convert a screen length in CSS pixels to a world length (inverse of
+    /// `world_length_to_screen`); divide device pixels by dpr first
+    pub fn screen_length_to_world(&self, len: f32) -> f32 {
+        len / self.zoom
+    }
+
+    /// Snap a world point onto the nearest whole device pixel
+    pub fn snap_to_device_pixel(&self, world_x: f32, world_y: f32) -> (f32, f32) {
+        let (screen_x, screen_y) = self.world_to_screen(world_x, world_y);
+        let snapped_x = (screen_x * self.dpr).round() / self.dpr;
+        let snapped_y = (screen_y * self.dpr).round() / self.dpr;
+        self.screen_to_world(snapped_x, snapped_y)
     }
 
     /// Convert screen coordinates to world coordinates
     pub fn screen_to_world(&self, screen_x: f32, screen_y: f32) -> (f32, f32) {
         // Reverse the view transform:
-        // 1. Translate from viewport center
-        // 2. Scale by zoom
+        // 1. Translate from viewport origin
+        // 2. Scale by zoom (mirrored on flipped axes)
         // 3. Translate by pan
         
-        let centered_x = screen_x - self.viewport_w / 2.0;
-        let centered_y = screen_y - self.viewport_h / 2.0;
+        let (origin_x, origin_y) = self.origin();
+        let centered_x = screen_x - origin_x;
+        let centered_y = screen_y - origin_y;
         
-        let world_x = centered_x / self.zoom + self.pan_x;
-        let world_y = centered_y / self.zoom + self.pan_y;
+        let (scale_x, scale_y) = self.axis_scale();
+        let world_x = centered_x / scale_x + self.pan_x;
+        let world_y = centered_y / scale_y + self.pan_y;
         
         (world_x, world_y)
     }
@@ -58,27 +171,69 @@ impl Camera {
     pub fn world_to_screen(&self, world_x: f32, world_y: f32) -> (f32, f32) {
         // Apply view transform:
         // 1. Translate by -pan
-        // 2. Scale by zoom
-        // 3. Translate to viewport center
+        // 2. Scale by zoom (mirrored on flipped axes)
+        // 3. Translate to viewport origin
         
         let translated_x = world_x - self.pan_x;
         let translated_y = world_y - self.pan_y;
         
-        let scaled_x = translated_x * self.zoom;
-        let scaled_y = translated_y * self.zoom;
+        let (scale_x, scale_y) = self.axis_scale();
+        let scaled_x = translated_x * scale_x;
+        let scaled_y = translated_y * scale_y;
         
-        let screen_x = scaled_x + self.viewport_w / 2.0;
-        let screen_y = scaled_y + self.viewport_h / 2.0;
+        let (origin_x, origin_y) = self.origin();
+        let screen_x = scaled_x + origin_x;
+        let screen_y = scaled_y + origin_y;
         
         (screen_x, screen_y)
     }
 
-    /// Get visible world bounds
+    /// Minimally pan (zooming out first if needed) so that at least `min_overlap`
+    /// (0..=1) of a world rect's area is on screen. Each axis is made to cover
+    /// `sqrt(min_overlap)` of the rect's extent (capped at the viewport extent,
+    /// zooming out about the pan point when it doesn't fit), shifting the view
+    /// only as far as needed. Returns whether the camera changed.
+    pub fn reveal_rect(&mut self, rect: (f32, f32, f32, f32), min_overlap: f32) -> bool {
+        let axis_fraction = min_overlap.clamp(0.0, 1.0).sqrt();
+        let (min_x, min_y, max_x, max_y) = rect;
+        let required_x = (max_x - min_x) * axis_fraction;
+        let required_y = (max_y - min_y) * axis_fraction;
+        let before = (self.zoom, self.pan_x, self.pan_y);
+
+        // Zoom out until the required extent fits on both axes
+        let (view_min_x, view_min_y, view_max_x, view_max_y) = self.get_visible_world_bounds();
+        let fit = ((view_max_x - view_min_x) / required_x).min((view_max_y - view_min_y) / required_y);
+        if fit < 1.0 {
+            self.zoom = (self.zoom * fit).clamp(self.min_zoom, self.max_zoom);
+        }
+
+        let (view_min_x, view_min_y, view_max_x, view_max_y) = self.get_visible_world_bounds();
+        self.pan_x += reveal_shift((min_x, max_x), (view_min_x, view_max_x), required_x);
+        self.pan_y += reveal_shift((min_y, max_y), (view_min_y, view_max_y), required_y);
+
+        (self.zoom, self.pan_x, self.pan_y) != before
+    }
+
+    /// Get visible world bounds (min < max even on flipped axes)
     pub fn get_visible_world_bounds(&self) -> (f32, f32, f32, f32) {
-        let (min_x, min_y) = self.screen_to_world(0.0, 0.0);
-        let (max_x, max_y) = self.screen_to_world(self.viewport_w, self.viewport_h);
+        let (x0, y0) = self.screen_to_world(0.0, 0.0);
+        let (x1, y1) = self.screen_to_world(self.viewport_w, self.viewport_h);
         
-        (min_x, min_y, max_x, max_y)
+        (x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1))
+    }
+}
+
+/// Shift of a view span so it overlaps a content span by `required`
+/// (or by as much of the view as possible), moving towards the content
+fn reveal_shift(content: (f32, f32), view: (f32, f32), required: f32) -> f32 {
+    let required = required.min(view.1 - view.0);
+    let overlap = content.1.min(view.1) - content.0.max(view.0);
+    if overlap >= required {
+        0.0
+    } else if content.0 + content.1 > view.0 + view.1 {
+        content.0 + required - view.1
+    } else {
+        content.1 - required - view.0
     }
 }
 
@@ -105,7 +260,7 @@ mod tests {
     #[test]
     fn test_roundtrip() {
         let mut camera = Camera::new();
-        camera.set(2.0, 100.0, 50.0, 800.0, 600.0, 1.0);
+        camera.set(2.0, 100.0, 50.0, 800.0, 600.0, 1.0, None, None, None, None);
         
         let (wx, wy) = camera.screen_to_world(400.0, 300.0);
         let (sx, sy) = camera.world_to_screen(wx, wy);
@@ -113,4 +268,115 @@ mod tests {
         assert!((sx - 400.0).abs() < 0.001);
         assert!((sy - 300.0).abs() < 0.001);
     }
+
+    #[test]
+    fn test_zero_zoom_rejected() {
+        let mut camera = Camera::new();
+        camera.set(0.0, 10.0, 20.0, 800.0, 600.0, 1.0, None, None, None, None);
+
+        let (wx, wy) = camera.screen_to_world(0.0, 0.0);
+        assert!(!wx.is_nan() && !wy.is_nan());
+        assert_eq!(camera.zoom(), 1.0);
+
+        camera.set(-2.0, 0.0, 0.0, 800.0, 600.0, 1.0, None, None, None, None);
+        assert_eq!(camera.zoom(), 1.0);
+    }
+
+    #[test]
+    fn test_zoom_clamped_to_limits() {
+        let mut camera = Camera::new();
+        camera.set(1000.0, 0.0, 0.0, 800.0, 600.0, 1.0, None, None, None, None);
+        assert_eq!(camera.zoom(), DEFAULT_MAX_ZOOM);
+
+        camera.set_zoom_limits(0.5, 4.0);
+        assert_eq!(camera.zoom(), 4.0);
+
+        camera.set(0.1, 0.0, 0.0, 800.0, 600.0, 1.0, None, None, None, None);
+        assert_eq!(camera.zoom(), 0.5);
+    }
+
+    #[test]
+    fn test_world_per_pixel() {
+        let mut camera = Camera::new();
+        assert_eq!(camera.world_per_pixel(), 1.0);
+
+        camera.set(2.0, 0.0, 0.0, 800.0, 600.0, 2.0, None, None, None, None);
+        assert_eq!(camera.world_per_pixel(), 0.25);
+    }
+
+    #[test]
+    fn test_snap_to_device_pixel() {
+        let mut camera = Camera::new();
+
+        // 2 device pixels per world unit: snaps to multiples of 0.5
+        camera.set(2.0, 0.0, 0.0, 800.0, 600.0, 1.0, None, None, None, None);
+        assert_eq!(camera.snap_to_device_pixel(0.3, -0.2), (0.5, 0.0));
+
+        // dpr 2 at zoom 2: 4 device pixels per world unit
+        camera.set(2.0, 0.0, 0.0, 800.0, 600.0, 2.0, None, None, None, None);
+        assert_eq!(camera.snap_to_device_pixel(0.3, 10.1), (0.25, 10.0));
+    }
+
+    #[test]
+    fn test_non_centered_origin() {
+        let mut camera = Camera::new();
+        // World origin drawn 100px right of a 200px sidebar, 50px down
+        camera.set(2.0, 0.0, 0.0, 800.0, 600.0, 1.0, Some(300.0), Some(50.0), None, None);
+
+        assert_eq!(camera.screen_to_world(300.0, 50.0), (0.0, 0.0));
+        assert_eq!(camera.screen_to_world(320.0, 90.0), (10.0, 20.0));
+        assert_eq!(camera.world_to_screen(10.0, 20.0), (320.0, 90.0));
+        assert_eq!(camera.get_visible_world_bounds(), (-150.0, -25.0, 250.0, 275.0));
+    }
+
+    #[test]
+    fn test_flip_y_roundtrip() {
+        let mut camera = Camera::new();
+        camera.set(2.0, 100.0, 50.0, 800.0, 600.0, 1.0, None, None, None, Some(true));
+
+        // World y grows upwards: a point above the pan point is drawn above center
+        let (sx, sy) = camera.world_to_screen(110.0, 60.0);
+        assert_eq!((sx, sy), (420.0, 280.0));
+
+        let (wx, wy) = camera.screen_to_world(sx, sy);
+        assert!((wx - 110.0).abs() < 0.001);
+        assert!((wy - 60.0).abs() < 0.001);
+
+        let (min_x, min_y, max_x, max_y) = camera.get_visible_world_bounds();
+        assert!(min_x < max_x && min_y < max_y);
+        assert_eq!((min_x, min_y, max_x, max_y), (-100.0, -100.0, 300.0, 200.0));
+    }
+
+    #[test]
+    fn test_reveal_rect() {
+        let mut camera = Camera::new();
+        camera.set(1.0, 0.0, 0.0, 800.0, 600.0, 1.0, None, None, None, None);
+
+        // Already fully on screen: untouched
+        assert!(!camera.reveal_rect((-100.0, -100.0, 100.0, 100.0), 1.0));
+
+        // 200x200 rect off to the right: pan just far enough for 25% (half per axis)
+        assert!(camera.reveal_rect((1000.0, 0.0, 1200.0, 200.0), 0.25));
+        assert_eq!((camera.zoom(), camera.pan_x(), camera.pan_y()), (1.0, 700.0, 0.0));
+        assert_eq!(camera.get_visible_world_bounds().2, 1100.0);
+
+        // Rect larger than the viewport zooms out so the required half fits
+        camera.set(1.0, 0.0, 0.0, 800.0, 600.0, 1.0, None, None, None, None);
+        assert!(camera.reveal_rect((-2400.0, -1000.0, 800.0, 1000.0), 0.25));
+        assert_eq!(camera.zoom(), 0.5);
+    }
+
+    #[test]
+    fn test_length_conversion() {
+        let mut camera = Camera::new();
+        camera.set(2.5, 10.0, 20.0, 800.0, 600.0, 2.0, None, None, None, Some(true));
+
+        assert_eq!(camera.world_length_to_screen(40.0), 100.0);
+        assert_eq!(camera.screen_length_to_world(100.0), 40.0);
+
+        // Matches the point transforms (flipping doesn't change lengths)
+        let (x0, y0) = camera.world_to_screen(0.0, 0.0);
+        let (x1, y1) = camera.world_to_screen(40.0, 40.0);
+        assert_eq!(((x1 - x0).abs(), (y1 - y0).abs()), (100.0, 100.0));
+    }
 }
@@ -0,0 +1,49 @@
+//! Errors returned by the strict batch and packed-data APIs
+//!
+//! Batch/packed methods validate their whole input before mutating and fail
+//! with an `EditorError` (thrown as a JS `Error`). Single-node methods on the
+//! hot path stay forgiving and ignore unknown handles.
+
+use std::fmt;
+use wasm_bindgen::prelude::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EditorError {
+    /// Packed data whose length is not a whole number of records
+    PackedLength { len: usize, stride: usize },
+    /// A batch referenced a handle that is not in the index
+    HandleNotFound(u32),
+    /// Paired batch arrays of different lengths
+    LengthMismatch { handles: usize, values: usize },
+}
+
+impl fmt::Display for EditorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EditorError::PackedLength { len, stride } => {
+                write!(f, "packed data length {len} not a multiple of {stride}")
+            }
+            EditorError::HandleNotFound(handle) => write!(f, "handle {handle} not found"),
+            EditorError::LengthMismatch { handles, values } => {
+                write!(f, "{handles} handles but {values} values")
+            }
+        }
+    }
+}
+
+impl std::error::Error for EditorError {}
+
+impl From<EditorError> for JsValue {
+    fn from(error: EditorError) -> Self {
+        JsError::new(&error.to_string()).into()
+    }
+}
+
+/// Check that packed data holds whole records of `stride` values
+pub(crate) fn check_packed_len(data: &[f32], stride: usize) -> Result<(), EditorError> {
+    if data.len().is_multiple_of(stride) {
+        Ok(())
+    } else {
+        Err(EditorError::PackedLength { len: data.len(), stride })
+    }
+}
@@ -0,0 +1,65 @@
+//! Geometry helpers shared by hit-testing and snapping
+
+/// Test whether a point lies inside an oriented rectangle
+///
+/// The rectangle is centered at (cx, cy) with full extents (w, h) and rotated
+/// by `rotation` radians. The point is transformed into the rectangle's local
+/// space and tested against the half extents (edges inclusive).
+pub fn point_in_oriented_rect(
+    px: f32,
+    py: f32,
+    cx: f32,
+    cy: f32,
+    w: f32,
+    h: f32,
+    rotation: f32,
+) -> bool {
+    let (sin, cos) = rotation.sin_cos();
+
+    // Rotate the point by -rotation around the center
+    let dx = px - cx;
+    let dy = py - cy;
+    let local_x = dx * cos + dy * sin;
+    let local_y = -dx * sin + dy * cos;
+
+    local_x.abs() <= w / 2.0 && local_y.abs() <= h / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_4;
+
+    #[test]
+    fn test_axis_aligned() {
+        assert!(point_in_oriented_rect(0.0, 0.0, 0.0, 0.0, 10.0, 4.0, 0.0));
+        assert!(point_in_oriented_rect(4.9, 1.9, 0.0, 0.0, 10.0, 4.0, 0.0));
+        assert!(!point_in_oriented_rect(5.1, 0.0, 0.0, 0.0, 10.0, 4.0, 0.0));
+        assert!(!point_in_oriented_rect(0.0, 2.1, 0.0, 0.0, 10.0, 4.0, 0.0));
+    }
+
+    #[test]
+    fn test_rotated_edges() {
+        // 10x10 square rotated 45 degrees around (100, 100):
+        // each edge's outward normal points along a diagonal
+        let (cx, cy) = (100.0, 100.0);
+        let normals = [(1.0f32, 1.0f32), (-1.0, 1.0), (-1.0, -1.0), (1.0, -1.0)];
+
+        for (nx, ny) in normals {
+            let len = (nx * nx + ny * ny).sqrt();
+            let (ux, uy) = (nx / len, ny / len);
+
+            let inside = 4.9;
+            let outside = 5.1;
+            assert!(point_in_oriented_rect(
+                cx + ux * inside, cy + uy * inside, cx, cy, 10.0, 10.0, FRAC_PI_4,
+            ));
+            assert!(!point_in_oriented_rect(
+                cx + ux * outside, cy + uy * outside, cx, cy, 10.0, 10.0, FRAC_PI_4,
+            ));
+        }
+
+        // Axis-aligned corner of the unrotated square is outside once rotated
+        assert!(!point_in_oriented_rect(104.9, 104.9, cx, cy, 10.0, 10.0, FRAC_PI_4));
+    }
+}
@@ -9,18 +9,112 @@
 //! - Viewport culling
 //! - Hit-testing queries
 //! - Snapping candidate generation
+//!
+//! Batch and packed-data methods (`load_scene_packed`, `translate_nodes`,
+//! `set_flags_many`, `set_virtual_targets`) are strict: they validate the whole
+//! input first and throw an `EditorError` without changing anything. Single-node
+//! methods (`upsert_node`, `remove_node`, `translate_node`, ...) are forgiving
+//! and ignore unknown handles, keeping hot paths free of error handling.
+//! `set_z_indices` is in between: it throws on mismatched array lengths but
+//! skips unknown handles, so a reorder can race node removal.
 
 mod spatial_index;
 mod camera;
 mod utils;
 mod smart_guides;
+mod geometry;
+mod snapping;
+mod error;
 
 use wasm_bindgen::prelude::*;
-use std::collections::HashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
 
 pub use spatial_index::SpatialIndex;
 pub use camera::Camera;
-pub use smart_guides::{AlignmentGuide, SpacingGuide, DistanceMeasurement};
+pub use smart_guides::{AlignmentGuide, SpacingGuide, DistanceMeasurement, CrossAlignment, SmartGuides};
+pub use snapping::{SnapResult, BoundsSnapResult, ResizeSnapResult};
+pub use error::EditorError;
+
+use smart_guides::AlignmentSession;
+use snapping::{OrientedRect, SnapOptions};
+
+// ============================================================================
+// Node Flags
+// ============================================================================
+
+/// Node is hidden: skipped by culling and hit-testing
+const FLAG_HIDDEN: u32 = 0x1;
+/// Node is locked: visible but skipped by hit-testing
+const FLAG_LOCKED: u32 = 0x2;
+/// Node is pinned (e.g. an infinite background): kept out of the grid and
+/// always returned by culling unless hidden. Area queries (rect queries,
+/// snapping, guides) skip it; point hit tests and bounds lookups still see it.
+const FLAG_PINNED: u32 = 0x4;
+
+// ============================================================================
+// Directions
+// ============================================================================
+
+const DIRECTION_LEFT: u8 = 0;
+const DIRECTION_RIGHT: u8 = 1;
+const DIRECTION_UP: u8 = 2;
+const DIRECTION_DOWN: u8 = 3;
+
+/// `nearest_edge_distance` value for an axis with no edge within the radius
+const NO_EDGE_DISTANCE: f32 = f32::MAX;
+
+/// `hit_test_edge` code for a point away from every edge; codes 1..=8 are the
+/// resize anchor plus one (1 = top-left corner, clockwise to 8 = left edge)
+const EDGE_INTERIOR: i32 = 0;
+
+// ============================================================================
+// Culling Results
+// ============================================================================
+
+/// Culled handles plus how many viewport candidates the hidden filter dropped
+#[wasm_bindgen]
+pub struct CullResult {
+    handles: Vec<u32>,
+    hidden_count: usize,
+}
+
+#[wasm_bindgen]
+impl CullResult {
+    #[wasm_bindgen(getter)]
+    pub fn handles(&self) -> Vec<u32> {
+        self.handles.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn visible_count(&self) -> usize {
+        self.handles.len()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn hidden_count(&self) -> usize {
+        self.hidden_count
+    }
+}
+
+/// Handles that entered and left the viewport since the previous `cull_diff`
+#[wasm_bindgen]
+pub struct CullDiff {
+    entered: Vec<u32>,
+    left: Vec<u32>,
+}
+
+#[wasm_bindgen]
+impl CullDiff {
+    #[wasm_bindgen(getter)]
+    pub fn entered(&self) -> Vec<u32> {
+        self.entered.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn left(&self) -> Vec<u32> {
+        self.left.clone()
+    }
+}
 
 // ============================================================================
 // Engine State
@@ -31,7 +125,43 @@ pub use smart_guides::{AlignmentGuide, SpacingGuide, DistanceMeasurement};
 pub struct EditorCore {
     spatial_index: SpatialIndex,
     camera: Camera,
-    node_flags: HashMap<u32, u32>,
+    node_flags: FxHashMap<u32, u32>,
+
+    /// Generation assigned to the next mutation
+    generation: u64,
+    /// Last-modified generation per live node
+    node_generations: FxHashMap<u32, u64>,
+    /// Removal generation per deleted node (tombstones)
+    removed_generations: FxHashMap<u32, u64>,
+
+    /// Insertion sequence assigned to the next newly created node
+    next_seq: u64,
+    /// Insertion sequence per live node (unchanged by updates)
+    node_seqs: FxHashMap<u32, u64>,
+
+    /// User tags (e.g. node kind) for tool-specific picking; untagged nodes are absent
+    node_tags: FxHashMap<u32, u32>,
+
+    /// Group membership; ungrouped nodes (group 0) are absent
+    node_groups: FxHashMap<u32, u32>,
+
+    /// Unrotated (width, height, rotation) of rotated nodes; their bounds are the AABB
+    node_orientations: FxHashMap<u32, (f32, f32, f32)>,
+
+    /// Decimal places snap results are rounded to; None returns them unrounded
+    snap_decimals: Option<u32>,
+
+    /// Non-node rects (group bounds, artboards) used as snap and guide targets
+    virtual_targets: Vec<(f32, f32, f32, f32)>,
+
+    /// Output of `cull_visible_buffered`, reused across frames
+    cull_buffer: Vec<u32>,
+
+    /// Visible set reported by the previous `cull_diff`
+    prev_visible: FxHashSet<u32>,
+
+    /// Cached reference lines for the current drag (see `begin_guide_session`)
+    guide_session: Option<AlignmentSession>,
 }
 
 #[wasm_bindgen]
@@ -44,12 +174,26 @@ impl EditorCore {
         Self {
             spatial_index: SpatialIndex::new(capacity),
             camera: Camera::new(),
-            node_flags: HashMap::with_capacity(capacity),
+            node_flags: FxHashMap::with_capacity_and_hasher(capacity, Default::default()),
+            generation: 0,
+            node_generations: FxHashMap::with_capacity_and_hasher(capacity, Default::default()),
+            removed_generations: FxHashMap::default(),
+            next_seq: 0,
+            node_seqs: FxHashMap::with_capacity_and_hasher(capacity, Default::default()),
+            node_tags: FxHashMap::default(),
+            node_groups: FxHashMap::default(),
+            node_orientations: FxHashMap::default(),
+            snap_decimals: None,
+            virtual_targets: Vec::new(),
+            cull_buffer: Vec::new(),
+            prev_visible: FxHashSet::default(),
+            guide_session: None,
         }
     }
 
     /// Insert or update a node in the spatial index
     #[wasm_bindgen]
+    #[allow(clippy::too_many_arguments)]
     pub fn upsert_node(
         &mut self,
         handle: u32,
@@ -57,11 +201,73 @@ impl EditorCore {
         min_y: f32,
         max_x: f32,
         max_y: f32,
-        z_index: i32,
+        z_index: f32,
         flags: u32,
     ) {
-        self.spatial_index.upsert(handle, min_x, min_y, max_x, max_y, z_index);
+        // A resize invalidates the stored rotated size; a move keeps it
+        if self.node_orientations.contains_key(&handle) {
+            let resized = self.spatial_index.get_bounds(handle).is_none_or(|old| {
+                !same_size((old.0, old.2), (min_x, max_x)) || !same_size((old.1, old.3), (min_y, max_y))
+            });
+            if resized {
+                self.node_orientations.remove(&handle);
+            }
+        }
+
+        let pinned = flags & FLAG_PINNED != 0;
+        self.spatial_index.upsert_placed(handle, (min_x, min_y, max_x, max_y), z_index, pinned);
         self.node_flags.insert(handle, flags);
+        self.mark_modified(handle);
+
+        if !self.node_seqs.contains_key(&handle) {
+            self.node_seqs.insert(handle, self.next_seq);
+            self.next_seq += 1;
+        }
+    }
+
+    /// Replace the whole scene from packed `[handle, min_x, min_y, max_x, max_y, z, flags, ...]`
+    /// records. `handle` and `flags` are u32 bit patterns in their f32 slots (write them
+    /// through a `Uint32Array` view of the same buffer), so any handle round-trips.
+    /// A handle listed more than once keeps its last record. Returns the number of
+    /// distinct nodes loaded; a length that is not a multiple of 7 is an error
+    /// and the current scene is left untouched.
+    #[wasm_bindgen]
+    pub fn load_scene_packed(&mut self, data: &[f32]) -> Result<usize, EditorError> {
+        error::check_packed_len(data, 7)?;
+
+        let count = data.len() / 7;
+        self.clear();
+        self.spatial_index.reserve(count);
+        self.node_flags.reserve(count);
+        self.node_generations.reserve(count);
+        self.node_seqs.reserve(count);
+
+        for r in data.chunks_exact(7) {
+            self.upsert_node(r[0].to_bits(), r[1], r[2], r[3], r[4], r[5], r[6].to_bits());
+        }
+        Ok(self.spatial_index.len())
+    }
+
+    /// Export every node as packed `[handle, min_x, min_y, max_x, max_y, z, flags, ...]`
+    /// records in the `load_scene_packed` format (handle and flags as u32 bits).
+    /// Order is unspecified.
+    #[wasm_bindgen]
+    pub fn export_all(&self) -> Vec<f32> {
+        let mut data = Vec::with_capacity(self.spatial_index.len() * 7);
+        for (handle, (min_x, min_y, max_x, max_y)) in self.spatial_index.iter_bounds() {
+            let z = self.spatial_index.get_z_index(handle).unwrap_or_default();
+            let flags = self.node_flags.get(&handle).copied().unwrap_or_default();
+            data.extend_from_slice(&[
+                f32::from_bits(handle),
+                min_x,
+                min_y,
+                max_x,
+                max_y,
+                z,
+                f32::from_bits(flags),
+            ]);
+        }
+        data
     }
 
     /// Remove a node from the spatial index
@@ -69,10 +275,171 @@ impl EditorCore {
     pub fn remove_node(&mut self, handle: u32) {
         self.spatial_index.remove(handle);
         self.node_flags.remove(&handle);
+        self.node_seqs.remove(&handle);
+        self.node_tags.remove(&handle);
+        self.node_groups.remove(&handle);
+        self.node_orientations.remove(&handle);
+        self.mark_removed(handle);
+    }
+
+    /// Move a node by a delta, preserving its z-index and flags
+    /// Returns false for unknown handles
+    #[wasm_bindgen]
+    pub fn translate_node(&mut self, handle: u32, dx: f32, dy: f32) -> bool {
+        let moved = self.spatial_index.translate(handle, dx, dy);
+        if moved {
+            self.mark_modified(handle);
+        }
+        moved
+    }
+
+    /// Change a node's z-index without resending its bounds; no grid cells are
+    /// recomputed, so reordering is cheap. Returns false for unknown handles
+    #[wasm_bindgen]
+    pub fn set_z_index(&mut self, handle: u32, z_index: f32) -> bool {
+        let updated = self.spatial_index.set_z_index(handle, z_index);
+        if updated {
+            self.mark_modified(handle);
+        }
+        updated
+    }
+
+    /// Assign z-indices to many nodes at once (e.g. normalizing z-order after a
+    /// layer reorder), pairing `handles[i]` with `z_values[i]`. Like `set_z_index`,
+    /// no grid cells are recomputed. Unknown handles are skipped and a repeated
+    /// handle takes its last value; returns the number of distinct nodes updated.
+    /// Fails without changing anything if the arrays differ in length.
+    #[wasm_bindgen]
+    pub fn set_z_indices(&mut self, handles: Vec<u32>, z_values: Vec<f32>) -> Result<usize, EditorError> {
+        if handles.len() != z_values.len() {
+            return Err(EditorError::LengthMismatch { handles: handles.len(), values: z_values.len() });
+        }
+
+        let mut updated = FxHashSet::default();
+        for (handle, z_index) in handles.into_iter().zip(z_values) {
+            if self.set_z_index(handle, z_index) {
+                updated.insert(handle);
+            }
+        }
+        Ok(updated.len())
+    }
+
+    /// Move several nodes by the same delta, preserving z-index and flags
+    /// Repeated handles move once; returns the number of nodes moved.
+    /// Fails without moving anything if any handle is unknown.
+    #[wasm_bindgen]
+    pub fn translate_nodes(&mut self, handles: Vec<u32>, dx: f32, dy: f32) -> Result<usize, EditorError> {
+        self.check_handles(&handles)?;
+
+        let mut seen = FxHashSet::default();
+        let mut moved = 0;
+        for handle in handles {
+            if seen.insert(handle) && self.spatial_index.translate(handle, dx, dy) {
+                self.mark_modified(handle);
+                moved += 1;
+            }
+        }
+        Ok(moved)
+    }
+
+    /// Clear then set flag bits on each listed node
+    /// Fails without changing any flags if any handle is unknown.
+    #[wasm_bindgen]
+    pub fn set_flags_many(&mut self, handles: Vec<u32>, set_mask: u32, clear_mask: u32) -> Result<(), EditorError> {
+        self.check_handles(&handles)?;
+
+        for handle in handles {
+            if let Some(&flags) = self.node_flags.get(&handle) {
+                self.store_flags(handle, (flags & !clear_mask) | set_mask);
+            }
+        }
+        Ok(())
+    }
+
+    /// Set a node's user tag (kept across updates until the node is removed)
+    /// Returns false for unknown handles
+    #[wasm_bindgen]
+    pub fn set_node_tag(&mut self, handle: u32, tag: u32) -> bool {
+        if !self.node_flags.contains_key(&handle) {
+            return false;
+        }
+        self.node_tags.insert(handle, tag);
+        true
+    }
+
+    /// Get a node's user tag, if one was set
+    #[wasm_bindgen]
+    pub fn get_node_tag(&self, handle: u32) -> Option<u32> {
+        self.node_tags.get(&handle).copied()
+    }
+
+    /// Set a node's group id (0 = no group)
+    /// Returns false for unknown handles
+    #[wasm_bindgen]
+    pub fn set_node_group(&mut self, handle: u32, group_id: u32) -> bool {
+        if !self.node_flags.contains_key(&handle) {
+            return false;
+        }
+        if group_id == 0 {
+            self.node_groups.remove(&handle);
+        } else {
+            self.node_groups.insert(handle, group_id);
+        }
+        true
+    }
+
+    /// Get a node's group id (0 for ungrouped or unknown nodes)
+    #[wasm_bindgen]
+    pub fn get_node_group(&self, handle: u32) -> u32 {
+        self.node_groups.get(&handle).copied().unwrap_or(0)
+    }
+
+    /// Set a node's rotation (radians) and unrotated size, so object snapping
+    /// uses its visual edges (and size matching its unrotated size) instead of
+    /// its bounds. The node's bounds stay its axis-aligned bounding box, centered
+    /// on the rotated rect. A rotation of 0 makes it axis-aligned again. An
+    /// `upsert_node` that changes the bounds' size clears the rotation (the old
+    /// unrotated size no longer fits), so call this again after a resize; moves
+    /// keep it. Returns false for unknown handles
+    #[wasm_bindgen]
+    pub fn set_node_rotation(&mut self, handle: u32, width: f32, height: f32, rotation: f32) -> bool {
+        if !self.node_flags.contains_key(&handle) {
+            return false;
+        }
+        if rotation == 0.0 {
+            self.node_orientations.remove(&handle);
+        } else {
+            self.node_orientations.insert(handle, (width, height, rotation));
+        }
+        true
+    }
+
+    /// Get a node's rotation in radians (0 for axis-aligned nodes)
+    #[wasm_bindgen]
+    pub fn get_node_rotation(&self, handle: u32) -> f32 {
+        self.node_orientations.get(&handle).map_or(0.0, |o| o.2)
+    }
+
+    /// Replace the virtual snap/guide targets from interleaved
+    /// `[min_x, min_y, max_x, max_y, ...]`
+    /// Fails, keeping the current targets, if the length is not a multiple of 4.
+    #[wasm_bindgen]
+    pub fn set_virtual_targets(&mut self, rects: &[f32]) -> Result<(), EditorError> {
+        error::check_packed_len(rects, 4)?;
+
+        self.virtual_targets = rects
+            .chunks_exact(4)
+            .map(|r| (r[0], r[1], r[2], r[3]))
+            .collect();
+        Ok(())
     }
 
     /// Update camera transform
+    /// `origin_x`/`origin_y` are the pixel position of the pan point within the
+    /// viewport; omit them to keep it centered. `flip_x`/`flip_y` mirror an axis
+    /// (e.g. Y-up world coordinates); omit them for the usual Y-down mapping.
     #[wasm_bindgen]
+    #[allow(clippy::too_many_arguments)]
     pub fn set_camera(
         &mut self,
         zoom: f32,
@@ -81,11 +448,72 @@ impl EditorCore {
         viewport_w: f32,
         viewport_h: f32,
         dpr: f32,
+        origin_x: Option<f32>,
+        origin_y: Option<f32>,
+        flip_x: Option<bool>,
+        flip_y: Option<bool>,
     ) {
-        self.camera.set(zoom, pan_x, pan_y, viewport_w, viewport_h, dpr);
+        self.camera.set(zoom, pan_x, pan_y, viewport_w, viewport_h, dpr, origin_x, origin_y, flip_x, flip_y);
+    }
+
+    /// Get the camera state as `[zoom, pan_x, pan_y, viewport_w, viewport_h, dpr]`
+    #[wasm_bindgen]
+    pub fn get_camera(&self) -> Vec<f32> {
+        let (zoom, pan_x, pan_y, viewport_w, viewport_h, dpr) = self.camera.params();
+        vec![zoom, pan_x, pan_y, viewport_w, viewport_h, dpr]
+    }
+
+    /// Get the current (clamped) camera zoom
+    #[wasm_bindgen]
+    pub fn get_zoom(&self) -> f32 {
+        self.camera.zoom()
+    }
+
+    /// Get the current camera pan x
+    #[wasm_bindgen]
+    pub fn get_pan_x(&self) -> f32 {
+        self.camera.pan_x()
+    }
+
+    /// Get the current camera pan y
+    #[wasm_bindgen]
+    pub fn get_pan_y(&self) -> f32 {
+        self.camera.pan_y()
+    }
+
+    /// Convert a world length to screen pixels: CSS pixels like `world_to_screen`,
+    /// or device pixels (scaled by dpr) with `device_pixels`
+    #[wasm_bindgen]
+    pub fn world_length_to_screen(&self, len: f32, device_pixels: Option<bool>) -> f32 {
+        let css = self.camera.world_length_to_screen(len);
+        if device_pixels.unwrap_or(false) {
+            css * self.camera.dpr()
+        } else {
+            css
+        }
+    }
+
+    /// Convert a screen length to world units: CSS pixels like `screen_to_world`,
+    /// or device pixels (divided by dpr) with `device_pixels`
+    #[wasm_bindgen]
+    pub fn screen_length_to_world(&self, len: f32, device_pixels: Option<bool>) -> f32 {
+        let css = if device_pixels.unwrap_or(false) {
+            len / self.camera.dpr()
+        } else {
+            len
+        };
+        self.camera.screen_length_to_world(css)
+    }
+
+    /// Set the allowed zoom range used to clamp `set_camera`
+    #[wasm_bindgen]
+    pub fn set_zoom_limits(&mut self, min: f32, max: f32) {
+        self.camera.set_zoom_limits(min, max);
     }
 
     /// Perform viewport culling and return visible node handles
+    /// Non-hidden pinned nodes are appended after the grid results, wherever
+    /// they are; the grid query never returns them, so each appears once.
     #[wasm_bindgen]
     pub fn cull_visible(&self) -> Vec<u32> {
         let visible_bounds = self.camera.get_visible_world_bounds();
@@ -96,20 +524,94 @@ impl EditorCore {
             visible_bounds.3,
         );
 
-        // Filter out hidden/locked nodes
-        visible.retain(|&handle| {
-            if let Some(&flags) = self.node_flags.get(&handle) {
-                // flag bits: bit 0 = hidden, bit 1 = locked
-                (flags & 0x1) == 0 // not hidden
-            } else {
-                true
-            }
-        });
-
+        self.finish_cull(&mut visible);
         visible
     }
 
-    /// Hit test at a world point, returns handles sorted by z-index (topmost first)
+    /// Perform viewport culling into an internal buffer reused across frames,
+    /// returning the number of visible handles. Read them without copying via
+    /// `new Uint32Array(memory.buffer, cull_buffer_ptr(), count)`. Read the view
+    /// right away and recreate it after any other engine call: any call, even
+    /// a read-only query, may allocate and grow wasm memory, which detaches it.
+    #[wasm_bindgen]
+    pub fn cull_visible_buffered(&mut self) -> usize {
+        let (min_x, min_y, max_x, max_y) = self.camera.get_visible_world_bounds();
+
+        let mut buffer = std::mem::take(&mut self.cull_buffer);
+        buffer.clear();
+        self.spatial_index.query_rect_into(min_x, min_y, max_x, max_y, &mut buffer);
+        self.finish_cull(&mut buffer);
+
+        self.cull_buffer = buffer;
+        self.cull_buffer.len()
+    }
+
+    /// Get a pointer to the handles written by `cull_visible_buffered`
+    /// Take it (and build the view) after the cull; see there for the view's lifetime.
+    #[wasm_bindgen]
+    pub fn cull_buffer_ptr(&self) -> *const u32 {
+        self.cull_buffer.as_ptr()
+    }
+
+    /// Perform viewport culling and report which handles entered or left the view
+    /// since the previous call (the first call reports everything as entered).
+    /// Uses the primary camera; costs O(visible + previously visible) per call.
+    /// Both lists are in ascending handle order.
+    #[wasm_bindgen]
+    pub fn cull_diff(&mut self) -> CullDiff {
+        let visible: FxHashSet<u32> = self.cull_visible().into_iter().collect();
+
+        let mut entered: Vec<u32> = visible
+            .iter()
+            .copied()
+            .filter(|handle| !self.prev_visible.contains(handle))
+            .collect();
+        let mut left: Vec<u32> = self
+            .prev_visible
+            .iter()
+            .copied()
+            .filter(|handle| !visible.contains(handle))
+            .collect();
+        entered.sort_unstable();
+        left.sort_unstable();
+
+        self.prev_visible = visible;
+        CullDiff { entered, left }
+    }
+
+    /// Perform viewport culling, also reporting how many nodes in view were hidden
+    #[wasm_bindgen]
+    pub fn cull_visible_with_stats(&self) -> CullResult {
+        let (min_x, min_y, max_x, max_y) = self.camera.get_visible_world_bounds();
+        let mut handles = self.spatial_index.query_rect(min_x, min_y, max_x, max_y);
+
+        let hidden_count = self.finish_cull(&mut handles);
+        CullResult { hidden_count, handles }
+    }
+
+    /// Check whether a world rect intersects the visible world bounds
+    /// (edge-touching counts, matching culling). The camera has no rotation,
+    /// so this is a plain AABB test.
+    #[wasm_bindgen]
+    pub fn is_rect_visible(&self, min_x: f32, min_y: f32, max_x: f32, max_y: f32) -> bool {
+        let (view_min_x, view_min_y, view_max_x, view_max_y) = self.camera.get_visible_world_bounds();
+        min_x <= view_max_x && view_min_x <= max_x && min_y <= view_max_y && view_min_y <= max_y
+    }
+
+    /// Rescue content lost off-screen: minimally pan (zooming out only if the
+    /// content can't otherwise fit) so at least `min_overlap` (0..=1) of the
+    /// content bounds' area is visible. Not a fit-to-content. Returns whether
+    /// the camera changed; an empty scene leaves it alone.
+    #[wasm_bindgen]
+    pub fn ensure_content_visible(&mut self, min_overlap: f32) -> bool {
+        match self.spatial_index.content_bounds() {
+            Some(bounds) => self.camera.reveal_rect(bounds, min_overlap),
+            None => false,
+        }
+    }
+
+    /// Hit test at a world point, returns handles sorted by z-index
+    /// (topmost first unless changed with `set_z_order_descending`)
     #[wasm_bindgen]
     pub fn hit_test_point(&self, world_x: f32, world_y: f32) -> Vec<u32> {
         let mut hits = self.spatial_index.query_point(world_x, world_y);
@@ -117,7 +619,7 @@ impl EditorCore {
         // Filter out hidden/locked nodes
         hits.retain(|&handle| {
             if let Some(&flags) = self.node_flags.get(&handle) {
-                (flags & 0x3) == 0 // not hidden and not locked
+                (flags & (FLAG_HIDDEN | FLAG_LOCKED)) == 0 // not hidden and not locked
             } else {
                 true
             }
@@ -126,6 +628,75 @@ impl EditorCore {
         hits
     }
 
+    /// `hit_test_point` with each hit's stored z-index, as interleaved
+    /// `[handle, z, ...]` pairs (see `query_rect_with_z` for the encoding)
+    #[wasm_bindgen]
+    pub fn hit_test_point_with_z(&self, world_x: f32, world_y: f32) -> Vec<i32> {
+        self.with_z(self.hit_test_point(world_x, world_y))
+    }
+
+    /// Hit test the topmost node at a world point and report which part of it the
+    /// point is on, as `[handle, edge_code]` (handle as u32 bits), or empty on a miss.
+    /// The point is on an edge when within `edge_tolerance` of it inside the node,
+    /// and on a corner when near two; the nearer side wins on thin nodes. Codes
+    /// match the resize anchors (see `snap_resize_anchor`) plus one: 1 = top-left,
+    /// 2 = top, 3 = top-right, 4 = right, 5 = bottom-right, 6 = bottom,
+    /// 7 = bottom-left, 8 = left, and 0 = interior.
+    #[wasm_bindgen]
+    pub fn hit_test_edge(&self, world_x: f32, world_y: f32, edge_tolerance: f32) -> Vec<i32> {
+        let Some(&handle) = self.hit_test_point(world_x, world_y).first() else {
+            return Vec::new();
+        };
+        let Some(bounds) = self.spatial_index.get_bounds(handle) else {
+            return Vec::new();
+        };
+        vec![handle as i32, edge_region(bounds, world_x, world_y, edge_tolerance)]
+    }
+
+    /// Get the topmost node at a world point whose user tag equals `tag`
+    /// Nodes with any of the `flags_mask` bits set (e.g. hidden | locked) are skipped.
+    #[wasm_bindgen]
+    pub fn hit_test_top_tagged(&self, world_x: f32, world_y: f32, tag: u32, flags_mask: u32) -> Option<u32> {
+        self.spatial_index
+            .query_point(world_x, world_y)
+            .into_iter()
+            .filter(|&handle| !self.has_flags(handle, flags_mask))
+            .find(|handle| self.node_tags.get(handle) == Some(&tag))
+    }
+
+    /// Test whether a world point lies inside one node, without a full hit test
+    /// (e.g. drop-target validation). Rotated nodes are tested against their
+    /// oriented rect, others against their bounds; edges count as inside and
+    /// flags are ignored. False for unknown handles
+    #[wasm_bindgen]
+    pub fn handle_contains_point(&self, handle: u32, world_x: f32, world_y: f32) -> bool {
+        let Some((min_x, min_y, max_x, max_y)) = self.spatial_index.get_bounds(handle) else {
+            return false;
+        };
+        match self.node_orientations.get(&handle) {
+            Some(&(width, height, rotation)) => {
+                let (cx, cy) = ((min_x + max_x) / 2.0, (min_y + max_y) / 2.0);
+                geometry::point_in_oriented_rect(world_x, world_y, cx, cy, width, height, rotation)
+            }
+            None => world_x >= min_x && world_x <= max_x && world_y >= min_y && world_y <= max_y,
+        }
+    }
+
+    /// Hit test at a screen point, converting to world space with the current camera
+    #[wasm_bindgen]
+    pub fn hit_test_screen(&self, screen_x: f32, screen_y: f32) -> Vec<u32> {
+        let (wx, wy) = self.camera.screen_to_world(screen_x, screen_y);
+        self.hit_test_point(wx, wy)
+    }
+
+    /// Query nodes within a screen-space rectangle (corners may be given in any order)
+    #[wasm_bindgen]
+    pub fn query_rect_screen(&self, sx0: f32, sy0: f32, sx1: f32, sy1: f32) -> Vec<u32> {
+        let (wx0, wy0) = self.camera.screen_to_world(sx0, sy0);
+        let (wx1, wy1) = self.camera.screen_to_world(sx1, sy1);
+        self.spatial_index.query_rect(wx0.min(wx1), wy0.min(wy1), wx0.max(wx1), wy0.max(wy1))
+    }
+
     /// Query nodes within a rectangle
     #[wasm_bindgen]
     pub fn query_rect(
@@ -138,79 +709,523 @@ impl EditorCore {
         self.spatial_index.query_rect(min_x, min_y, max_x, max_y)
     }
 
-    /// Query nodes near a point (for snapping)
+    /// Query nodes touching a rectangle in one pass, split by containment:
+    /// interleaved `[handle, mode, ...]` pairs with the handle's u32 bits as i32
+    /// and mode 0 = fully contained, 1 = intersecting only. Pairs are z-sorted
+    /// like hit-testing (topmost first by default).
     #[wasm_bindgen]
-    pub fn query_near(&self, world_x: f32, world_y: f32, radius: f32) -> Vec<u32> {
-        self.spatial_index.query_near(world_x, world_y, radius)
+    pub fn query_rect_both(&self, min_x: f32, min_y: f32, max_x: f32, max_y: f32) -> Vec<i32> {
+        let mut hits = self.spatial_index.query_rect(min_x, min_y, max_x, max_y);
+        self.spatial_index.sort_by_z(&mut hits);
+
+        let mut result = Vec::with_capacity(hits.len() * 2);
+        for handle in hits {
+            let contained = self.spatial_index.get_bounds(handle).is_some_and(|b| {
+                b.0 >= min_x && b.1 >= min_y && b.2 <= max_x && b.3 <= max_y
+            });
+            result.push(handle as i32);
+            result.push(if contained { 0 } else { 1 });
+        }
+        result
     }
 
-    /// Convert screen coordinates to world coordinates
+    /// Query nodes within a rectangle, nearest first by center-to-center distance
+    /// from the rectangle's center (ties by handle)
     #[wasm_bindgen]
-    pub fn screen_to_world(&self, screen_x: f32, screen_y: f32) -> Vec<f32> {
-        let (wx, wy) = self.camera.screen_to_world(screen_x, screen_y);
-        vec![wx, wy]
+    pub fn query_rect_by_distance(&self, min_x: f32, min_y: f32, max_x: f32, max_y: f32) -> Vec<u32> {
+        let (cx, cy) = ((min_x + max_x) / 2.0, (min_y + max_y) / 2.0);
+        let mut hits: Vec<(f32, u32)> = self
+            .spatial_index
+            .query_rect(min_x, min_y, max_x, max_y)
+            .into_iter()
+            .filter_map(|handle| {
+                let b = self.spatial_index.get_bounds(handle)?;
+                let (dx, dy) = ((b.0 + b.2) / 2.0 - cx, (b.1 + b.3) / 2.0 - cy);
+                Some((dx * dx + dy * dy, handle))
+            })
+            .collect();
+
+        hits.sort_unstable_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+        hits.into_iter().map(|(_, handle)| handle).collect()
     }
 
-    /// Convert world coordinates to screen coordinates
+    /// Distinct group ids of nodes intersecting a rectangle, ascending
+    /// (e.g. to promote a marquee selection to whole groups). The no-group id 0
+    /// is only included when `include_ungrouped` is set and an ungrouped node is hit.
     #[wasm_bindgen]
-    pub fn world_to_screen(&self, world_x: f32, world_y: f32) -> Vec<f32> {
-        let (sx, sy) = self.camera.world_to_screen(world_x, world_y);
-        vec![sx, sy]
+    pub fn groups_in_rect(
+        &self,
+        min_x: f32,
+        min_y: f32,
+        max_x: f32,
+        max_y: f32,
+        include_ungrouped: Option<bool>,
+    ) -> Vec<u32> {
+        let include_ungrouped = include_ungrouped.unwrap_or(false);
+        let groups: FxHashSet<u32> = self
+            .spatial_index
+            .query_rect(min_x, min_y, max_x, max_y)
+            .into_iter()
+            .map(|handle| self.get_node_group(handle))
+            .filter(|&group| group != 0 || include_ungrouped)
+            .collect();
+
+        let mut groups: Vec<u32> = groups.into_iter().collect();
+        groups.sort_unstable();
+        groups
     }
 
-    /// Get node count (for debugging)
+    /// Query nodes within a rectangle with their stored z-index, as interleaved
+    /// `[handle, z, ...]` pairs: the handle's u32 bits and the z-index's f32 bits,
+    /// both as i32 (read z through a `Float32Array` view of the same buffer)
     #[wasm_bindgen]
-    pub fn get_node_count(&self) -> usize {
-        self.spatial_index.len()
+    pub fn query_rect_with_z(&self, min_x: f32, min_y: f32, max_x: f32, max_y: f32) -> Vec<i32> {
+        self.with_z(self.spatial_index.query_rect(min_x, min_y, max_x, max_y))
     }
 
-    /// Clear all nodes
+    /// Query visible nodes within a rectangle that were inserted at or after `seq`
+    /// (see `current_seq`); updating an existing node does not change its sequence
     #[wasm_bindgen]
-    pub fn clear(&mut self) {
-        self.spatial_index.clear();
-        self.node_flags.clear();
+    pub fn query_rect_created_after(
+        &self,
+        min_x: f32,
+        min_y: f32,
+        max_x: f32,
+        max_y: f32,
+        seq: u64,
+    ) -> Vec<u32> {
+        self.spatial_index
+            .query_rect(min_x, min_y, max_x, max_y)
+            .into_iter()
+            .filter(|handle| self.node_seqs.get(handle).is_some_and(|&s| s >= seq))
+            .filter(|&handle| !self.has_flags(handle, FLAG_HIDDEN))
+            .collect()
     }
-}
 
-// ============================================================================
-// Snap Result Struct
-// ============================================================================
+    /// Get grid cell densities within a rectangle as interleaved
+    /// `[cell_x, cell_y, count, ...]` triples (non-empty cells only)
+    #[wasm_bindgen]
+    pub fn query_rect_by_cell(
+        &self,
+        min_x: f32,
+        min_y: f32,
+        max_x: f32,
+        max_y: f32,
+    ) -> Vec<i32> {
+        let counts = self.spatial_index.cell_counts_in_rect(min_x, min_y, max_x, max_y);
 
-#[wasm_bindgen]
-pub struct SnapResult {
-    snapped: bool,
-    x: f32,
-    y: f32,
-    guide_count: usize,
-}
+        let mut result = Vec::with_capacity(counts.len() * 3);
+        for (cell_x, cell_y, count) in counts {
+            result.extend_from_slice(&[cell_x, cell_y, count as i32]);
+        }
+        result
+    }
 
-#[wasm_bindgen]
-impl SnapResult {
-    #[wasm_bindgen(getter)]
-    pub fn snapped(&self) -> bool {
-        self.snapped
+    /// Aggregate a selection in one pass: `[min_x, min_y, max_x, max_y, count, area]`
+    /// where min/max is the union of the nodes' bounds, count the number of distinct
+    /// known handles and area the sum of their bounds' areas. Unknown handles are
+    /// skipped; with none known the result is all zeros.
+    #[wasm_bindgen]
+    pub fn selection_extent(&self, handles: Vec<u32>) -> Vec<f32> {
+        let mut seen = FxHashSet::default();
+        let mut extent: Option<(f32, f32, f32, f32)> = None;
+        let mut area = 0.0;
+
+        for handle in handles {
+            let Some(b) = self.spatial_index.get_bounds(handle) else {
+                continue;
+            };
+            if !seen.insert(handle) {
+                continue;
+            }
+            area += (b.2 - b.0) * (b.3 - b.1);
+            extent = Some(match extent {
+                Some(e) => (e.0.min(b.0), e.1.min(b.1), e.2.max(b.2), e.3.max(b.3)),
+                None => b,
+            });
+        }
+
+        let (min_x, min_y, max_x, max_y) = extent.unwrap_or_default();
+        vec![min_x, min_y, max_x, max_y, seen.len() as f32, area]
     }
 
-    #[wasm_bindgen(getter)]
-    pub fn x(&self) -> f32 {
-        self.x
+    /// Bounds `[min_x, min_y, max_x, max_y]` of all nodes; None when the scene is empty
+    #[wasm_bindgen]
+    pub fn content_bounds(&self) -> Option<Vec<f32>> {
+        self.spatial_index
+            .content_bounds()
+            .map(|(min_x, min_y, max_x, max_y)| vec![min_x, min_y, max_x, max_y])
     }
 
-    #[wasm_bindgen(getter)]
-    pub fn y(&self) -> f32 {
-        self.y
+    /// Bounds `[min_x, min_y, max_x, max_y]` of the densest part of the scene, for
+    /// "zoom to content" that ignores stray distant nodes. Nodes are binned by
+    /// the grid cell holding their center, and the densest cells are taken until
+    /// they hold `percentile` (0..=1) of all nodes; see
+    /// `SpatialIndex::dense_region_bounds`. None when the scene is empty.
+    #[wasm_bindgen]
+    pub fn dense_region_bounds(&self, percentile: f32) -> Option<Vec<f32>> {
+        self.spatial_index
+            .dense_region_bounds(percentile)
+            .map(|(min_x, min_y, max_x, max_y)| vec![min_x, min_y, max_x, max_y])
     }
 
-    #[wasm_bindgen(getter)]
-    pub fn guide_count(&self) -> usize {
-        self.guide_count
+    /// Find a spot for a `width` x `height` rect near a desired top-left corner
+    /// that overlaps no node (touching edges is fine), e.g. to place pasted or
+    /// generated content. Returns `[min_x, min_y, max_x, max_y]`, or None if
+    /// nothing is free within `max_rings`.
+    ///
+    /// Candidates lie on a lattice of `step` around (`near_x`, `near_y`) and
+    /// are tried in a fixed order, so every client gets the same answer for the
+    /// same scene: ring 0 is the desired spot itself; ring r is the square of
+    /// lattice offsets with max(|i|, |j|) = r, walked clockwise from its top-left
+    /// corner (top row left to right, right column downwards, bottom row right to
+    /// left, left column upwards). The first free candidate wins.
+    #[wasm_bindgen]
+    pub fn find_free_rect(
+        &self,
+        width: f32,
+        height: f32,
+        near_x: f32,
+        near_y: f32,
+        step: f32,
+        max_rings: u32,
+    ) -> Option<Vec<f32>> {
+        if step.is_nan() || step <= 0.0 {
+            return None;
+        }
+
+        let is_free = |x: f32, y: f32| {
+            let (max_x, max_y) = (x + width, y + height);
+            self.spatial_index.query_rect(x, y, max_x, max_y).into_iter().all(|handle| {
+                self.spatial_index
+                    .get_bounds(handle)
+                    .is_none_or(|b| !(b.0 < max_x && x < b.2 && b.1 < max_y && y < b.3))
+            })
+        };
+
+        (0..=max_rings as i32)
+            .flat_map(ring_offsets)
+            .map(|(i, j)| (near_x + i as f32 * step, near_y + j as f32 * step))
+            .find(|&(x, y)| is_free(x, y))
+            .map(|(x, y)| vec![x, y, x + width, y + height])
+    }
+
+    /// Query nodes near a point (for snapping)
+    #[wasm_bindgen]
+    pub fn query_near(&self, world_x: f32, world_y: f32, radius: f32) -> Vec<u32> {
+        self.spatial_index.query_near(world_x, world_y, radius)
+    }
+
+    /// Get the nodes whose bounds overlap a node's bounds (edge-touching counts),
+    /// z-sorted like hit-testing. Empty for unknown handles.
+    #[wasm_bindgen]
+    pub fn overlapping_with(&self, handle: u32, exclude_self: bool) -> Vec<u32> {
+        let (min_x, min_y, max_x, max_y) = match self.spatial_index.get_bounds(handle) {
+            Some(bounds) => bounds,
+            None => return Vec::new(),
+        };
+
+        let mut hits = self.spatial_index.query_rect(min_x, min_y, max_x, max_y);
+        if exclude_self {
+            hits.retain(|&h| h != handle);
+        }
+        self.spatial_index.sort_by_z(&mut hits);
+        hits
+    }
+
+    /// Check whether two nodes' bounds overlap (edge-touching counts)
+    /// Returns false if either handle is unknown
+    #[wasm_bindgen]
+    pub fn intersects(&self, a: u32, b: u32) -> bool {
+        match (self.spatial_index.get_bounds(a), self.spatial_index.get_bounds(b)) {
+            (Some(a), Some(b)) => a.0 <= b.2 && b.0 <= a.2 && a.1 <= b.3 && b.1 <= a.3,
+            _ => false,
+        }
+    }
+
+    /// Convert screen coordinates to world coordinates
+    #[wasm_bindgen]
+    pub fn screen_to_world(&self, screen_x: f32, screen_y: f32) -> Vec<f32> {
+        let (wx, wy) = self.camera.screen_to_world(screen_x, screen_y);
+        vec![wx, wy]
+    }
+
+    /// Convert world coordinates to screen coordinates
+    #[wasm_bindgen]
+    pub fn world_to_screen(&self, world_x: f32, world_y: f32) -> Vec<f32> {
+        let (sx, sy) = self.camera.world_to_screen(world_x, world_y);
+        vec![sx, sy]
+    }
+
+    /// Find the nearest node in a direction (0=left, 1=right, 2=up, 3=down) for
+    /// keyboard navigation. Candidates are visible nodes whose center lies in that
+    /// half-plane; the one with the greatest perpendicular overlap with the source
+    /// wins, ties broken by center distance.
+    #[wasm_bindgen]
+    pub fn nearest_in_direction(&self, from_handle: u32, direction: u8) -> Option<u32> {
+        let (from_min_x, from_min_y, from_max_x, from_max_y) =
+            self.spatial_index.get_bounds(from_handle)?;
+        let from_cx = (from_min_x + from_max_x) / 2.0;
+        let from_cy = (from_min_y + from_max_y) / 2.0;
+
+        // (handle, overlap, distance)
+        let mut best: Option<(u32, f32, f32)> = None;
+
+        for (handle, (min_x, min_y, max_x, max_y)) in self.spatial_index.iter_bounds() {
+            if handle == from_handle || self.has_flags(handle, FLAG_HIDDEN) {
+                continue;
+            }
+
+            let cx = (min_x + max_x) / 2.0;
+            let cy = (min_y + max_y) / 2.0;
+
+            let (in_direction, overlap) = match direction {
+                DIRECTION_LEFT => (cx < from_cx, from_max_y.min(max_y) - from_min_y.max(min_y)),
+                DIRECTION_RIGHT => (cx > from_cx, from_max_y.min(max_y) - from_min_y.max(min_y)),
+                DIRECTION_UP => (cy < from_cy, from_max_x.min(max_x) - from_min_x.max(min_x)),
+                DIRECTION_DOWN => (cy > from_cy, from_max_x.min(max_x) - from_min_x.max(min_x)),
+                _ => return None,
+            };
+            if !in_direction {
+                continue;
+            }
+
+            let overlap = overlap.max(0.0);
+            let distance = ((cx - from_cx).powi(2) + (cy - from_cy).powi(2)).sqrt();
+
+            let better = match best {
+                None => true,
+                Some((best_handle, best_overlap, best_distance)) => {
+                    overlap > best_overlap
+                        || (overlap == best_overlap && distance < best_distance)
+                        || (overlap == best_overlap && distance == best_distance && handle < best_handle)
+                }
+            };
+            if better {
+                best = Some((handle, overlap, distance));
+            }
+        }
+
+        best.map(|(handle, _, _)| handle)
+    }
+
+    /// Test whether a world point lies inside an oriented rectangle
+    /// centered at (cx, cy) with size (w, h) and rotation in radians
+    #[wasm_bindgen]
+    pub fn point_in_oriented_rect(
+        px: f32,
+        py: f32,
+        cx: f32,
+        cy: f32,
+        w: f32,
+        h: f32,
+        rotation: f32,
+    ) -> bool {
+        geometry::point_in_oriented_rect(px, py, cx, cy, w, h, rotation)
+    }
+
+    /// Get how many grid cells a node spans (for detecting large-node fan-out)
+    #[wasm_bindgen]
+    pub fn cell_count_for(&self, handle: u32) -> usize {
+        self.spatial_index.cell_count_for(handle)
+    }
+
+    /// Get the grid cell `[cell_x, cell_y]` containing a world point
+    #[wasm_bindgen]
+    pub fn world_to_cell(&self, world_x: f32, world_y: f32) -> Vec<i32> {
+        let (cell_x, cell_y) = self.spatial_index.world_to_cell(world_x, world_y);
+        vec![cell_x, cell_y]
+    }
+
+    /// Set whether hit-test results are topmost-first (true, default) or bottommost-first
+    #[wasm_bindgen]
+    pub fn set_z_order_descending(&mut self, descending: bool) {
+        self.spatial_index.set_z_order_descending(descending);
+    }
+
+    /// Set the grid cell width and height in world units (default 256 x 256)
+    /// Re-indexes all nodes; invalid sizes are ignored
+    #[wasm_bindgen]
+    pub fn set_cell_size(&mut self, width: f32, height: f32) {
+        self.spatial_index.set_cell_size(width, height);
+    }
+
+    /// Set the cell span above which nodes bypass the grid and are always tested
+    /// (capped internally so huge nodes never enumerate unbounded cell ranges)
+    #[wasm_bindgen]
+    pub fn set_overflow_cell_threshold(&mut self, threshold: usize) {
+        self.spatial_index.set_overflow_cell_threshold(threshold);
+    }
+
+    /// Get how many world units map to one device pixel (for 1px overlay strokes)
+    #[wasm_bindgen]
+    pub fn world_per_pixel(&self) -> f32 {
+        self.camera.world_per_pixel()
+    }
+
+    /// Round a world point to the nearest whole device pixel at the current
+    /// zoom/dpr, returning `[x, y]` in world space. Always snaps (no threshold).
+    #[wasm_bindgen]
+    pub fn snap_to_pixel(&self, world_x: f32, world_y: f32) -> Vec<f32> {
+        let (x, y) = self.camera.snap_to_device_pixel(world_x, world_y);
+        vec![x, y]
+    }
+
+    /// Get node count (for debugging)
+    #[wasm_bindgen]
+    pub fn get_node_count(&self) -> usize {
+        self.spatial_index.len()
+    }
+
+    /// Count nodes with the given flag bit set (e.g. hidden nodes for a layers
+    /// panel). A mask of several bits counts nodes with any of them; 0 counts none.
+    /// A pass over every node's flags.
+    #[wasm_bindgen]
+    pub fn count_by_flag(&self, flag_bit: u32) -> usize {
+        self.node_flags.values().filter(|&&flags| flags & flag_bit != 0).count()
+    }
+
+    /// Count all nodes (the total `count_by_flag` counts are out of)
+    #[wasm_bindgen]
+    pub fn count_total(&self) -> usize {
+        self.node_flags.len()
+    }
+
+    /// Get every node handle (order unspecified; for diagnostics and resync)
+    #[wasm_bindgen]
+    pub fn all_handles(&self) -> Vec<u32> {
+        self.spatial_index.iter_bounds().map(|(handle, _)| handle).collect()
+    }
+
+    /// Get every node whose hidden bit is clear (e.g. for Ctrl+A)
+    #[wasm_bindgen]
+    pub fn select_all_visible(&self) -> Vec<u32> {
+        self.spatial_index
+            .iter_bounds()
+            .map(|(handle, _)| handle)
+            .filter(|&handle| !self.has_flags(handle, FLAG_HIDDEN))
+            .collect()
+    }
+
+    /// Estimate the heap memory held by the engine, in bytes: the spatial index
+    /// plus per-node maps and reused buffers. Capacity-based and ignoring
+    /// allocator overhead, so it is representative rather than exact.
+    #[wasm_bindgen]
+    pub fn estimated_memory_bytes(&self) -> usize {
+        self.spatial_index.estimated_memory_bytes()
+            + utils::map_bytes::<u32, u32>(self.node_flags.capacity())
+            + utils::map_bytes::<u32, u64>(self.node_generations.capacity())
+            + utils::map_bytes::<u32, u64>(self.removed_generations.capacity())
+            + utils::map_bytes::<u32, u64>(self.node_seqs.capacity())
+            + utils::map_bytes::<u32, u32>(self.node_tags.capacity())
+            + utils::map_bytes::<u32, u32>(self.node_groups.capacity())
+            + utils::map_bytes::<u32, (f32, f32, f32)>(self.node_orientations.capacity())
+            + utils::map_bytes::<u32, ()>(self.prev_visible.capacity())
+            + utils::vec_bytes::<(f32, f32, f32, f32)>(self.virtual_targets.capacity())
+            + utils::vec_bytes::<u32>(self.cull_buffer.capacity())
+    }
+
+    /// Reclaim memory held by the index after large deletions
+    /// Rehashes internal maps, so call it on idle or after bulk deletes, not per frame
+    #[wasm_bindgen]
+    pub fn gc(&mut self) {
+        self.spatial_index.gc();
+        self.node_flags.shrink_to_fit();
+        self.node_generations.shrink_to_fit();
+        self.removed_generations.shrink_to_fit();
+        self.node_seqs.shrink_to_fit();
+        self.node_tags.shrink_to_fit();
+        self.node_groups.shrink_to_fit();
+        self.node_orientations.shrink_to_fit();
+    }
+
+    /// Clear all nodes
+    #[wasm_bindgen]
+    pub fn clear(&mut self) {
+        let handles: Vec<u32> = self.node_generations.keys().copied().collect();
+        for handle in handles {
+            self.mark_removed(handle);
+        }
+
+        self.spatial_index.clear();
+        self.node_flags.clear();
+        self.node_seqs.clear();
+        self.node_tags.clear();
+        self.node_groups.clear();
+        self.node_orientations.clear();
+    }
+
+    /// Get the generation that the next mutation will be stamped with
+    /// Snapshot this each frame and pass it to `nodes_modified_since` next frame
+    #[wasm_bindgen]
+    pub fn current_generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Get the insertion sequence that the next newly created node will receive
+    /// Snapshot this before a paste or import to find its nodes afterwards
+    #[wasm_bindgen]
+    pub fn current_seq(&self) -> u64 {
+        self.next_seq
+    }
+
+    /// Get handles inserted or updated at or after the given generation
+    #[wasm_bindgen]
+    pub fn nodes_modified_since(&self, gen: u64) -> Vec<u32> {
+        self.node_generations
+            .iter()
+            .filter(|&(_, &node_gen)| node_gen >= gen)
+            .map(|(&handle, _)| handle)
+            .collect()
+    }
+
+    /// Get handles removed at or after the given generation (and not re-inserted since)
+    #[wasm_bindgen]
+    pub fn nodes_removed_since(&self, gen: u64) -> Vec<u32> {
+        self.removed_generations
+            .iter()
+            .filter(|&(_, &removed_gen)| removed_gen >= gen)
+            .map(|(&handle, _)| handle)
+            .collect()
+    }
+
+    /// Forget removals before the given generation, returning how many were dropped
+    /// Call it with the oldest generation any consumer still syncs from (e.g. the
+    /// snapshot its last `nodes_removed_since` call used); otherwise tombstones,
+    /// one per removed node and per node at `clear`, accumulate for the session.
+    #[wasm_bindgen]
+    pub fn prune_removed_before(&mut self, gen: u64) -> usize {
+        let before = self.removed_generations.len();
+        self.removed_generations.retain(|_, &mut removed_gen| removed_gen >= gen);
+        before - self.removed_generations.len()
     }
 }
 
+// ============================================================================
+// Snapping & Smart Guides
+// ============================================================================
+
 #[wasm_bindgen]
 impl EditorCore {
+    /// Round coordinates returned by the snap methods to `decimals` places
+    /// (0 = whole units), so grid snaps come back as clean values. Pass None
+    /// to turn rounding off again (the default). Decimals are capped at 9.
+    #[wasm_bindgen]
+    pub fn set_snap_rounding(&mut self, decimals: Option<u32>) {
+        self.snap_decimals = decimals.map(|d| d.min(snapping::MAX_SNAP_DECIMALS));
+    }
+
     /// Snap a point to nearby edges/centers
+    /// Each axis takes the nearest candidate within threshold (see `snap_candidates`).
+    /// Earlier versions took the last match instead, so node edges always beat
+    /// the grid; now a closer grid line wins. `guide_count` still counts every
+    /// candidate within threshold.
+    /// Pass the dragged node as `exclude_handle` so it doesn't snap to itself.
+    /// With `target_flag_mask`, only nodes whose `flags & mask == target_flag_match`
+    /// are object targets (e.g. only frames for the frame tool); mask 0 (the
+    /// default) considers all nodes, whatever the match.
     #[wasm_bindgen]
+    #[allow(clippy::too_many_arguments)]
     pub fn snap_point(
         &self,
         world_x: f32,
@@ -219,75 +1234,386 @@ impl EditorCore {
         grid_size: f32,
         enable_grid: bool,
         enable_objects: bool,
+        exclude_handle: Option<u32>,
+        target_flag_mask: Option<u32>,
+        target_flag_match: Option<u32>,
     ) -> SnapResult {
-        let mut snapped_x = world_x;
-        let mut snapped_y = world_y;
-        let mut snapped = false;
-        let mut guide_count = 0;
-
-        // Grid snapping
-        if enable_grid && grid_size > 0.0 {
-            let grid_x = (world_x / grid_size).round() * grid_size;
-            let grid_y = (world_y / grid_size).round() * grid_size;
-            
-            if (world_x - grid_x).abs() < snap_threshold {
-                snapped_x = grid_x;
-                snapped = true;
-                guide_count += 1;
-            }
-            if (world_y - grid_y).abs() < snap_threshold {
-                snapped_y = grid_y;
-                snapped = true;
-                guide_count += 1;
-            }
+        self.snap(
+            world_x,
+            world_y,
+            &SnapOptions {
+                threshold: snap_threshold,
+                grid_size,
+                grid_x: enable_grid,
+                grid_y: enable_grid,
+                objects: enable_objects,
+                axis_lock: snapping::AXIS_LOCK_NONE,
+                exclude_handle,
+                target_mask: target_flag_mask.unwrap_or(0),
+                target_match: target_flag_match.unwrap_or(0),
+            },
+        )
+    }
+
+    /// Constrain a point to the nearest line through an anchor at a multiple of
+    /// `increment_deg` degrees (45 by default, for Shift-drawing), returning the
+    /// projected `[x, y]`. A point on the anchor is returned unchanged, as is
+    /// any point for a non-positive increment.
+    #[wasm_bindgen]
+    pub fn snap_to_diagonal(
+        &self,
+        anchor_x: f32,
+        anchor_y: f32,
+        point_x: f32,
+        point_y: f32,
+        increment_deg: Option<f32>,
+    ) -> Vec<f32> {
+        let increment = increment_deg.unwrap_or(45.0);
+        if increment.is_nan() || increment <= 0.0 {
+            return vec![point_x, point_y];
         }
 
-        // Object snapping
-        if enable_objects {
-            let nearby = self.query_near(world_x, world_y, snap_threshold * 3.0);
-            
-            // Get bounds for nearby nodes and find snap candidates
-            for &handle in &nearby {
-                if let Some(bounds) = self.spatial_index.get_bounds(handle) {
-                    // Snap to edges and center
-                    let edges_x = [bounds.0, (bounds.0 + bounds.2) / 2.0, bounds.2];
-                    let edges_y = [bounds.1, (bounds.1 + bounds.3) / 2.0, bounds.3];
-
-                    for &edge_x in &edges_x {
-                        if (world_x - edge_x).abs() < snap_threshold {
-                            snapped_x = edge_x;
-                            snapped = true;
-                            guide_count += 1;
-                        }
-                    }
+        let (x, y) = snapping::constrain_to_angle(
+            (anchor_x, anchor_y),
+            (point_x, point_y),
+            (increment as f64).to_radians(),
+        );
+        match self.snap_decimals {
+            Some(decimals) => vec![snapping::round_to(x, decimals), snapping::round_to(y, decimals)],
+            None => vec![x, y],
+        }
+    }
 
-                    for &edge_y in &edges_y {
-                        if (world_y - edge_y).abs() < snap_threshold {
-                            snapped_y = edge_y;
-                            snapped = true;
-                            guide_count += 1;
-                        }
-                    }
-                }
-            }
+    /// Snap a point with grid snapping toggled per axis
+    /// (e.g. snap x to columns while leaving y continuous)
+    #[wasm_bindgen]
+    #[allow(clippy::too_many_arguments)]
+    pub fn snap_point_axes(
+        &self,
+        world_x: f32,
+        world_y: f32,
+        snap_threshold: f32,
+        grid_size: f32,
+        enable_grid_x: bool,
+        enable_grid_y: bool,
+        enable_objects: bool,
+    ) -> SnapResult {
+        self.snap(
+            world_x,
+            world_y,
+            &SnapOptions {
+                threshold: snap_threshold,
+                grid_size,
+                grid_x: enable_grid_x,
+                grid_y: enable_grid_y,
+                objects: enable_objects,
+                axis_lock: snapping::AXIS_LOCK_NONE,
+                exclude_handle: None,
+                target_mask: 0,
+                target_match: 0,
+            },
+        )
+    }
+
+    /// Snap a point with one axis held fixed (shift-drag)
+    /// `axis_lock`: 0 = none, 1 = x locked, 2 = y locked. The locked coordinate
+    /// is returned unchanged; only the free axis snaps.
+    #[wasm_bindgen]
+    #[allow(clippy::too_many_arguments)]
+    pub fn snap_point_locked(
+        &self,
+        world_x: f32,
+        world_y: f32,
+        snap_threshold: f32,
+        grid_size: f32,
+        enable_grid: bool,
+        enable_objects: bool,
+        axis_lock: u8,
+    ) -> SnapResult {
+        self.snap(
+            world_x,
+            world_y,
+            &SnapOptions {
+                threshold: snap_threshold,
+                grid_size,
+                grid_x: enable_grid,
+                grid_y: enable_grid,
+                objects: enable_objects,
+                axis_lock,
+                exclude_handle: None,
+                target_mask: 0,
+                target_match: 0,
+            },
+        )
+    }
+
+    /// Get the raw snap candidates for a point as interleaved
+    /// `[axis, position, source, distance, ...]` quadruples, where axis is
+    /// 0 = x / 1 = y and source is 0 = grid, 1 = edge, 2 = center, 3 = guide
+    /// (virtual target). Candidates are those strictly within `radius` on their axis.
+    #[wasm_bindgen]
+    pub fn snap_candidates(
+        &self,
+        world_x: f32,
+        world_y: f32,
+        radius: f32,
+        enable_grid: bool,
+        grid_size: f32,
+    ) -> Vec<f32> {
+        let options = SnapOptions {
+            threshold: radius,
+            grid_size,
+            grid_x: enable_grid,
+            grid_y: enable_grid,
+            objects: true,
+            axis_lock: snapping::AXIS_LOCK_NONE,
+            exclude_handle: None,
+            target_mask: 0,
+            target_match: 0,
+        };
+        let candidates = self.point_snap_candidates(world_x, world_y, radius, &options);
+
+        let mut result = Vec::with_capacity(candidates.len() * 4);
+        for c in candidates {
+            result.extend_from_slice(&[c.axis as f32, c.position, c.source as f32, c.distance]);
+        }
+        result
+    }
+
+    /// Snap the moving corner of a box being resized from a fixed anchor corner
+    /// Each axis takes the nearest of: nearby node edges/centers and virtual targets
+    /// (as `snap_point`), and corner positions that make the box's width/height
+    /// equal a nearby node's. Nearby means intersecting the box grown by its larger
+    /// side. The result reports which dimensions matched a node's size.
+    #[wasm_bindgen]
+    #[allow(clippy::too_many_arguments)]
+    pub fn snap_resize(
+        &self,
+        anchor_x: f32,
+        anchor_y: f32,
+        corner_x: f32,
+        corner_y: f32,
+        snap_threshold: f32,
+        exclude_handle: Option<u32>,
+        target_flag_mask: Option<u32>,
+        target_flag_match: Option<u32>,
+    ) -> ResizeSnapResult {
+        let options = SnapOptions {
+            threshold: snap_threshold,
+            grid_size: 0.0,
+            grid_x: false,
+            grid_y: false,
+            objects: true,
+            axis_lock: snapping::AXIS_LOCK_NONE,
+            exclude_handle,
+            target_mask: target_flag_mask.unwrap_or(0),
+            target_match: target_flag_match.unwrap_or(0),
+        };
+        let result = self.resize_snap((anchor_x, anchor_y), (corner_x, corner_y), &options, true);
+        match self.snap_decimals {
+            Some(decimals) => result.rounded(decimals),
+            None => result,
         }
+    }
+
+    /// Snap one of a box's eight resize handles (or its center) dragged to a pointer
+    /// `anchor`: 0 = top-left, 1 = top, 2 = top-right, 3 = right, 4 = bottom-right,
+    /// 5 = bottom, 6 = bottom-left, 7 = left, 8 = center. Corners snap as in
+    /// `snap_resize` with the opposite corner fixed; edge handles only move (and
+    /// snap) their own axis; the center snaps as a point and moves the whole box.
+    /// The result's x/y is the dragged anchor's snapped position and min/max the
+    /// resulting box. Target flags scope targets as in `snap_point`.
+    /// None for an unknown anchor.
+    #[wasm_bindgen]
+    #[allow(clippy::too_many_arguments)]
+    pub fn snap_resize_anchor(
+        &self,
+        min_x: f32,
+        min_y: f32,
+        max_x: f32,
+        max_y: f32,
+        anchor: u8,
+        pointer_x: f32,
+        pointer_y: f32,
+        snap_threshold: f32,
+        exclude_handle: Option<u32>,
+        target_flag_mask: Option<u32>,
+        target_flag_match: Option<u32>,
+    ) -> Option<ResizeSnapResult> {
+        let drag = snapping::ResizeDrag::new((min_x, min_y, max_x, max_y), anchor, (pointer_x, pointer_y))?;
+        let options = SnapOptions {
+            threshold: snap_threshold,
+            grid_size: 0.0,
+            grid_x: false,
+            grid_y: false,
+            objects: true,
+            axis_lock: drag.axis_lock,
+            exclude_handle,
+            target_mask: target_flag_mask.unwrap_or(0),
+            target_match: target_flag_match.unwrap_or(0),
+        };
 
-        SnapResult {
-            snapped,
-            x: snapped_x,
-            y: snapped_y,
-            guide_count,
+        let result = drag.resolve(self.resize_snap(drag.fixed, drag.moving, &options, drag.is_resize()));
+        Some(match self.snap_decimals {
+            Some(decimals) => result.rounded(decimals),
+            None => result,
+        })
+    }
+
+    /// Dry-run `snap_point`, returning every candidate it considers as interleaved
+    /// `[axis, position, source, distance, delta, winner, ...]` sextuples
+    /// (encodings as in `snap_candidates`; delta is position minus the input
+    /// coordinate; winner is 1 for the candidate picked on its axis, else 0).
+    /// Positions and deltas are rounded as `snap_point` rounds its result (see
+    /// `set_snap_rounding`); distances are not, since they pick the winners.
+    #[wasm_bindgen]
+    pub fn snap_debug(
+        &self,
+        world_x: f32,
+        world_y: f32,
+        snap_threshold: f32,
+        grid_size: f32,
+        enable_grid: bool,
+        enable_objects: bool,
+    ) -> Vec<f32> {
+        let options = SnapOptions {
+            threshold: snap_threshold,
+            grid_size,
+            grid_x: enable_grid,
+            grid_y: enable_grid,
+            objects: enable_objects,
+            axis_lock: snapping::AXIS_LOCK_NONE,
+            exclude_handle: None,
+            target_mask: 0,
+            target_match: 0,
+        };
+        let candidates = self.point_snap_candidates(world_x, world_y, snap_threshold * 3.0, &options);
+
+        let winners = [
+            snapping::nearest_candidate_index(&candidates, snapping::AXIS_X),
+            snapping::nearest_candidate_index(&candidates, snapping::AXIS_Y),
+        ];
+
+        let mut result = Vec::with_capacity(candidates.len() * 6);
+        for (i, c) in candidates.iter().enumerate() {
+            let origin = if c.axis == snapping::AXIS_X { world_x } else { world_y };
+            let position = match self.snap_decimals {
+                Some(decimals) => snapping::round_to(c.position, decimals),
+                None => c.position,
+            };
+            let is_winner = winners.contains(&Some(i));
+            result.extend_from_slice(&[
+                c.axis as f32,
+                position,
+                c.source as f32,
+                c.distance,
+                position - origin,
+                if is_winner { 1.0 } else { 0.0 },
+            ]);
         }
+        result
+    }
+
+    /// Snap a moving box by its edges and center
+    /// Each axis independently takes the nearest candidate within threshold
+    /// (grid lines, nearby node edges/centers, virtual targets).
+    /// With `size_factor` k the threshold grows with the box:
+    /// `snap_threshold + k * min(width, height)`, capped at `max_threshold` (never
+    /// below `snap_threshold`). Omitting k keeps the constant threshold.
+    /// `target_flag_mask`/`target_flag_match` scope object targets as in `snap_point`.
+    #[wasm_bindgen]
+    #[allow(clippy::too_many_arguments)]
+    pub fn snap_bounds(
+        &self,
+        min_x: f32,
+        min_y: f32,
+        max_x: f32,
+        max_y: f32,
+        snap_threshold: f32,
+        grid_size: f32,
+        enable_grid: bool,
+        enable_objects: bool,
+        size_factor: Option<f32>,
+        max_threshold: Option<f32>,
+        target_flag_mask: Option<u32>,
+        target_flag_match: Option<u32>,
+    ) -> BoundsSnapResult {
+        let bounds = (min_x, min_y, max_x, max_y);
+        let threshold = snapping::size_scaled_threshold(
+            snap_threshold,
+            bounds,
+            size_factor.unwrap_or(0.0),
+            max_threshold.unwrap_or(f32::INFINITY),
+        );
+
+        self.snap_box(
+            bounds,
+            &SnapOptions {
+                threshold,
+                grid_size,
+                grid_x: enable_grid,
+                grid_y: enable_grid,
+                objects: enable_objects,
+                axis_lock: snapping::AXIS_LOCK_NONE,
+                exclude_handle: None,
+                target_mask: target_flag_mask.unwrap_or(0),
+                target_match: target_flag_match.unwrap_or(0),
+            },
+        )
+    }
+
+    /// Snap a point, returning primitives instead of a `SnapResult` object:
+    /// `[snapped (0/1), x, y, guide_count, dx, dy]`
+    /// Avoids allocating a JS wrapper object per call in hot drag loops
+    #[wasm_bindgen]
+    #[allow(clippy::too_many_arguments)]
+    pub fn snap_point_flat(
+        &self,
+        world_x: f32,
+        world_y: f32,
+        snap_threshold: f32,
+        grid_size: f32,
+        enable_grid: bool,
+        enable_objects: bool,
+        exclude_handle: Option<u32>,
+        target_flag_mask: Option<u32>,
+        target_flag_match: Option<u32>,
+    ) -> Vec<f32> {
+        let result = self.snap_point(
+            world_x,
+            world_y,
+            snap_threshold,
+            grid_size,
+            enable_grid,
+            enable_objects,
+            exclude_handle,
+            target_flag_mask,
+            target_flag_match,
+        );
+
+        vec![
+            if result.snapped() { 1.0 } else { 0.0 },
+            result.x(),
+            result.y(),
+            result.guide_count() as f32,
+            result.x() - world_x,
+            result.y() - world_y,
+        ]
     }
 
     /// Calculate alignment guides (Figma-style)
-    /// Shows when edges or centers of objects align
+    /// Shows when edges or centers of objects align.
+    /// Handles in `exclude` (e.g. the rest of a dragged selection) are not used as references.
+    /// With `include_viewport`, the visible world bounds' edges and center lines are
+    /// targets too; those guides follow the node guides and report `is_viewport`.
     #[wasm_bindgen]
     pub fn calculate_alignment_guides(
         &self,
         moving_handle: u32,
         visible_handles: Vec<u32>,
         threshold: f32,
+        exclude: Option<Vec<u32>>,
+        include_viewport: Option<bool>,
     ) -> Vec<AlignmentGuide> {
         // Get bounds for moving node
         let moving_bounds = match self.spatial_index.get_bounds(moving_handle) {
@@ -295,57 +1621,247 @@ impl EditorCore {
             None => return Vec::new(),
         };
 
-        // Get bounds for all visible nodes (excluding the moving node)
-        let mut all_bounds = Vec::new();
-        for &handle in &visible_handles {
-            if handle != moving_handle {
-                if let Some(bounds) = self.spatial_index.get_bounds(handle) {
-                    all_bounds.push(bounds);
-                }
-            }
-        }
+        // Get bounds for all visible nodes (excluding the moving set)
+        let all_bounds = self.reference_bounds(moving_handle, &visible_handles, exclude);
 
-        smart_guides::calculate_alignment_guides(moving_bounds, &all_bounds, threshold)
+        let mut guides = smart_guides::calculate_alignment_guides(moving_bounds, &all_bounds, threshold);
+        if include_viewport.unwrap_or(false) {
+            let viewport = self.camera.get_visible_world_bounds();
+            guides.extend(smart_guides::calculate_viewport_guides(moving_bounds, viewport, threshold));
+        }
+        guides
     }
 
-    /// Calculate spacing guides (Figma-style)
-    /// Shows when spacing between objects is equal
+    /// Start a drag's alignment guide session: the reference nodes' edges and
+    /// centers (visible handles minus the moving node and `exclude`, plus
+    /// virtual targets) are cached once, so `update_guide_session` only matches
+    /// the moving box against them each frame. Replaces any previous session.
     #[wasm_bindgen]
-    pub fn calculate_spacing_guides(
-        &self,
+    pub fn begin_guide_session(
+        &mut self,
         moving_handle: u32,
         visible_handles: Vec<u32>,
-    ) -> Vec<SpacingGuide> {
-        // Get bounds for moving node
-        let moving_bounds = match self.spatial_index.get_bounds(moving_handle) {
-            Some(bounds) => bounds,
-            None => return Vec::new(),
-        };
+        threshold: f32,
+        exclude: Option<Vec<u32>>,
+    ) {
+        let all_bounds = self.reference_bounds(moving_handle, &visible_handles, exclude);
+        self.guide_session = Some(AlignmentSession::new(&all_bounds, threshold));
+    }
 
-        // Get bounds for all visible nodes (excluding the moving node)
-        let mut all_bounds = Vec::new();
-        for &handle in &visible_handles {
-            if handle != moving_handle {
-                if let Some(bounds) = self.spatial_index.get_bounds(handle) {
-                    all_bounds.push(bounds);
-                }
-            }
+    /// Alignment guides for the moving box at its new position, against the
+    /// references cached by `begin_guide_session` (same guides as
+    /// `calculate_alignment_guides`). Empty when no session is active.
+    #[wasm_bindgen]
+    pub fn update_guide_session(&self, min_x: f32, min_y: f32, max_x: f32, max_y: f32) -> Vec<AlignmentGuide> {
+        match &self.guide_session {
+            Some(session) => session.guides((min_x, min_y, max_x, max_y)),
+            None => Vec::new(),
         }
+    }
 
-        smart_guides::calculate_spacing_guides(moving_bounds, &all_bounds)
+    /// End the guide session, freeing its cache
+    #[wasm_bindgen]
+    pub fn end_guide_session(&mut self) {
+        self.guide_session = None;
     }
 
-    /// Calculate distance measurements
-    /// Shows distance from object to nearest siblings or parent bounds
+    /// Calculate alignment guides with the threshold given in screen pixels
+    /// The threshold is converted to world units using the current camera zoom
     #[wasm_bindgen]
-    pub fn calculate_distance_measurements(
+    pub fn calculate_alignment_guides_px(
         &self,
         moving_handle: u32,
         visible_handles: Vec<u32>,
-        parent_x: Option<f32>,
+        threshold_px: f32,
+        exclude: Option<Vec<u32>>,
+        include_viewport: Option<bool>,
+    ) -> Vec<AlignmentGuide> {
+        let threshold = threshold_px / self.camera.zoom();
+        self.calculate_alignment_guides(moving_handle, visible_handles, threshold, exclude, include_viewport)
+    }
+
+    /// Calculate alignment guides against a fixed reference rectangle
+    /// Lets margins, print areas and safe zones act as guide targets without being nodes
+    #[wasm_bindgen]
+    pub fn calculate_alignment_guides_to_rect(
+        &self,
+        moving_handle: u32,
+        ref_min_x: f32,
+        ref_min_y: f32,
+        ref_max_x: f32,
+        ref_max_y: f32,
+        threshold: f32,
+    ) -> Vec<AlignmentGuide> {
+        // Get bounds for moving node
+        let moving_bounds = match self.spatial_index.get_bounds(moving_handle) {
+            Some(bounds) => bounds,
+            None => return Vec::new(),
+        };
+
+        let reference = [(ref_min_x, ref_min_y, ref_max_x, ref_max_y)];
+
+        smart_guides::calculate_alignment_guides(moving_bounds, &reference, threshold)
+    }
+
+    /// Check whether two nodes' edges or centers align within threshold, without
+    /// generating guides. `alignment_type` uses the guide encoding (0 = left,
+    /// 1 = right, 2 = top, 3 = bottom, 4 = center x, 5 = center y). The threshold
+    /// is inclusive (0 checks exact alignment). Returns false for unknown handles or types.
+    #[wasm_bindgen]
+    pub fn is_aligned(&self, a: u32, b: u32, alignment_type: u8, threshold: f32) -> bool {
+        match (self.spatial_index.get_bounds(a), self.spatial_index.get_bounds(b)) {
+            (Some(a), Some(b)) => smart_guides::is_aligned(a, b, alignment_type, threshold),
+            _ => false,
+        }
+    }
+
+    /// Measure from a node's nearest edges to a point (e.g. the cursor)
+    /// Returns an empty vec for unknown handles
+    #[wasm_bindgen]
+    pub fn measure_to_point(&self, handle: u32, world_x: f32, world_y: f32) -> Vec<DistanceMeasurement> {
+        match self.spatial_index.get_bounds(handle) {
+            Some(bounds) => smart_guides::calculate_point_measurements(bounds, (world_x, world_y)),
+            None => Vec::new(),
+        }
+    }
+
+    /// Find the nearest left/right edge to `world_x` among the given nodes
+    /// Returns nothing when no edge is within threshold
+    #[wasm_bindgen]
+    pub fn nearest_edge_x(
+        &self,
+        world_x: f32,
+        visible_handles: Vec<u32>,
+        threshold: f32,
+    ) -> Option<f32> {
+        self.nearest_edge(world_x, &visible_handles, threshold, |b| [b.0, b.2])
+    }
+
+    /// Find the nearest top/bottom edge to `world_y` among the given nodes
+    /// Returns nothing when no edge is within threshold
+    #[wasm_bindgen]
+    pub fn nearest_edge_y(
+        &self,
+        world_y: f32,
+        visible_handles: Vec<u32>,
+        threshold: f32,
+    ) -> Option<f32> {
+        self.nearest_edge(world_y, &visible_handles, threshold, |b| [b.1, b.3])
+    }
+
+    /// Signed distance from a point to the nearest node edge on each axis,
+    /// whether or not a snap would fire: `[dx, dy]` with `dx = edge_x - world_x`.
+    /// Only edges within `radius` of the point count (nodes found with `query_near`,
+    /// hidden nodes skipped); an axis without one reports `f32::MAX`.
+    #[wasm_bindgen]
+    pub fn nearest_edge_distance(&self, world_x: f32, world_y: f32, radius: f32) -> Vec<f32> {
+        let mut best_x = NO_EDGE_DISTANCE;
+        let mut best_y = NO_EDGE_DISTANCE;
+
+        for handle in self.spatial_index.query_near(world_x, world_y, radius) {
+            if self.has_flags(handle, FLAG_HIDDEN) {
+                continue;
+            }
+            let Some((min_x, min_y, max_x, max_y)) = self.spatial_index.get_bounds(handle) else {
+                continue;
+            };
+            for delta in [min_x - world_x, max_x - world_x] {
+                if delta.abs() <= radius && delta.abs() < best_x.abs() {
+                    best_x = delta;
+                }
+            }
+            for delta in [min_y - world_y, max_y - world_y] {
+                if delta.abs() <= radius && delta.abs() < best_y.abs() {
+                    best_y = delta;
+                }
+            }
+        }
+
+        vec![best_x, best_y]
+    }
+
+    /// Calculate spacing guides (Figma-style)
+    /// Shows when spacing between objects is equal, within `tolerance` world units
+    /// (default 0.5), and, given the parent bounds, when the margins to both parent
+    /// edges are equal. Handles in `exclude` are not used as references.
+    #[wasm_bindgen]
+    #[allow(clippy::too_many_arguments)]
+    pub fn calculate_spacing_guides(
+        &self,
+        moving_handle: u32,
+        visible_handles: Vec<u32>,
+        exclude: Option<Vec<u32>>,
+        tolerance: Option<f32>,
+        parent_x: Option<f32>,
+        parent_y: Option<f32>,
+        parent_width: Option<f32>,
+        parent_height: Option<f32>,
+    ) -> Vec<SpacingGuide> {
+        // Get bounds for moving node
+        let moving_bounds = match self.spatial_index.get_bounds(moving_handle) {
+            Some(bounds) => bounds,
+            None => return Vec::new(),
+        };
+
+        // Get bounds for all visible nodes (excluding the moving set)
+        let all_bounds = self.reference_bounds(moving_handle, &visible_handles, exclude);
+
+        // Build parent bounds if provided
+        let parent_bounds = match (parent_x, parent_y, parent_width, parent_height) {
+            (Some(x), Some(y), Some(w), Some(h)) => Some((x, y, w, h)),
+            _ => None,
+        };
+
+        let tolerance = tolerance.unwrap_or(smart_guides::DEFAULT_SPACING_TOLERANCE);
+        smart_guides::calculate_spacing_guides(moving_bounds, &all_bounds, parent_bounds, tolerance)
+    }
+
+    /// Calculate spacing guides with the tolerance given in screen pixels
+    /// (default 0.5px). The tolerance is converted to world units using the
+    /// current camera zoom.
+    #[wasm_bindgen]
+    #[allow(clippy::too_many_arguments)]
+    pub fn calculate_spacing_guides_px(
+        &self,
+        moving_handle: u32,
+        visible_handles: Vec<u32>,
+        exclude: Option<Vec<u32>>,
+        tolerance_px: Option<f32>,
+        parent_x: Option<f32>,
+        parent_y: Option<f32>,
+        parent_width: Option<f32>,
+        parent_height: Option<f32>,
+    ) -> Vec<SpacingGuide> {
+        let tolerance_px = tolerance_px.unwrap_or(smart_guides::DEFAULT_SPACING_TOLERANCE);
+        let tolerance = tolerance_px / self.camera.zoom();
+        self.calculate_spacing_guides(
+            moving_handle,
+            visible_handles,
+            exclude,
+            Some(tolerance),
+            parent_x,
+            parent_y,
+            parent_width,
+            parent_height,
+        )
+    }
+
+    /// Calculate distance measurements
+    /// Shows distance from object to nearest siblings or parent bounds.
+    /// With `always_measure_parent`, parent edges are measured even when siblings exist.
+    /// Handles in `exclude` are not used as references.
+    #[wasm_bindgen]
+    #[allow(clippy::too_many_arguments)]
+    pub fn calculate_distance_measurements(
+        &self,
+        moving_handle: u32,
+        visible_handles: Vec<u32>,
+        parent_x: Option<f32>,
         parent_y: Option<f32>,
         parent_width: Option<f32>,
         parent_height: Option<f32>,
+        always_measure_parent: Option<bool>,
+        exclude: Option<Vec<u32>>,
     ) -> Vec<DistanceMeasurement> {
         // Get bounds for moving node
         let moving_bounds = match self.spatial_index.get_bounds(moving_handle) {
@@ -353,15 +1869,66 @@ impl EditorCore {
             None => return Vec::new(),
         };
 
-        // Get bounds for all visible nodes (excluding the moving node)
-        let mut all_bounds = Vec::new();
-        for &handle in &visible_handles {
-            if handle != moving_handle {
-                if let Some(bounds) = self.spatial_index.get_bounds(handle) {
-                    all_bounds.push(bounds);
-                }
-            }
+        // Get bounds for all visible nodes (excluding the moving set)
+        let all_bounds = self.reference_bounds(moving_handle, &visible_handles, exclude);
+
+        // Build parent bounds if provided
+        let parent_bounds = match (parent_x, parent_y, parent_width, parent_height) {
+            (Some(x), Some(y), Some(w), Some(h)) => Some((x, y, w, h)),
+            _ => None,
+        };
+
+        smart_guides::calculate_distance_measurements(
+            moving_bounds,
+            &all_bounds,
+            parent_bounds,
+            always_measure_parent.unwrap_or(false),
+        )
+    }
+
+    /// Audit spacing across a set: the gap from each node to its adjacent node to
+    /// the right and below, as interleaved `[a, b, axis, gap, ...]` quadruples
+    /// (handles as u32 bits in their f32 slots, read through a `Uint32Array`
+    /// view; axis 0 = horizontal, 1 = vertical). Adjacent means nearest on that
+    /// side with overlapping perpendicular extent; see `smart_guides::audit_spacing`.
+    /// Unknown handles are skipped.
+    #[wasm_bindgen]
+    pub fn audit_spacing(&self, visible_handles: Vec<u32>) -> Vec<f32> {
+        let nodes: Vec<smart_guides::HandleBounds> = visible_handles
+            .iter()
+            .filter_map(|&handle| Some((handle, self.spatial_index.get_bounds(handle)?)))
+            .collect();
+
+        let gaps = smart_guides::audit_spacing(&nodes);
+        let mut result = Vec::with_capacity(gaps.len() * 4);
+        for (a, b, axis, gap) in gaps {
+            result.extend_from_slice(&[f32::from_bits(a), f32::from_bits(b), axis as f32, gap]);
         }
+        result
+    }
+
+    /// Calculate alignment, spacing and distance guides in one call
+    /// Also pairs a vertical and horizontal center guide into a cross alignment
+    /// when a reference node is centered on the moving node.
+    #[wasm_bindgen]
+    #[allow(clippy::too_many_arguments)]
+    pub fn compute_smart_guides(
+        &self,
+        moving_handle: u32,
+        visible_handles: Vec<u32>,
+        threshold: f32,
+        parent_x: Option<f32>,
+        parent_y: Option<f32>,
+        parent_width: Option<f32>,
+        parent_height: Option<f32>,
+        always_measure_parent: Option<bool>,
+        exclude: Option<Vec<u32>>,
+        tolerance: Option<f32>,
+    ) -> Option<SmartGuides> {
+        let moving_bounds = self.spatial_index.get_bounds(moving_handle)?;
+
+        // Get bounds for all visible nodes (excluding the moving set)
+        let all_bounds = self.reference_bounds(moving_handle, &visible_handles, exclude);
 
         // Build parent bounds if provided
         let parent_bounds = match (parent_x, parent_y, parent_width, parent_height) {
@@ -369,6 +1936,1394 @@ impl EditorCore {
             _ => None,
         };
 
-        smart_guides::calculate_distance_measurements(moving_bounds, &all_bounds, parent_bounds)
+        Some(smart_guides::compute_smart_guides(
+            moving_bounds,
+            &all_bounds,
+            threshold,
+            tolerance.unwrap_or(smart_guides::DEFAULT_SPACING_TOLERANCE),
+            parent_bounds,
+            always_measure_parent.unwrap_or(false),
+        ))
+    }
+}
+
+// ============================================================================
+// Internal Helpers
+// ============================================================================
+
+/// Axis-aligned node bounds and rotated node rects, as object snap targets
+type SnapTargets = (Vec<(f32, f32, f32, f32)>, Vec<OrientedRect>);
+
+/// Lattice offsets of square ring `r` in `find_free_rect` order: clockwise from
+/// the top-left corner, each offset once
+fn ring_offsets(r: i32) -> impl Iterator<Item = (i32, i32)> {
+    let top = (-r..=r).map(move |i| (i, -r));
+    let right = (-r + 1..=r).map(move |j| (r, j));
+    let bottom = (-r..r).rev().map(move |i| (i, r));
+    let left = (-r + 1..r).rev().map(move |j| (-r, j));
+    top.chain(right).chain(bottom).chain(left) // ring 0 is just the top row's (0, 0)
+}
+
+/// Whether two `(min, max)` spans have the same length, allowing for the f32
+/// rounding a move introduces at their coordinates' magnitude
+fn same_size(a: (f32, f32), b: (f32, f32)) -> bool {
+    let magnitude = [a.0, a.1, b.0, b.1].iter().fold(1.0f32, |m, v| m.max(v.abs()));
+    ((a.1 - a.0) - (b.1 - b.0)).abs() <= 4.0 * f32::EPSILON * magnitude
+}
+
+/// `hit_test_edge` code for a point inside `bounds`
+fn edge_region(bounds: (f32, f32, f32, f32), x: f32, y: f32, tolerance: f32) -> i32 {
+    let (min_x, min_y, max_x, max_y) = bounds;
+
+    // Side near the point per axis: Some(true) = max side, Some(false) = min side
+    let side = |value: f32, min: f32, max: f32| {
+        let (to_min, to_max) = (value - min, max - value);
+        match (to_min <= tolerance, to_max <= tolerance) {
+            (false, false) => None,
+            (true, true) => Some(to_max < to_min),
+            (near_min, _) => Some(!near_min),
+        }
+    };
+
+    let anchor = match (side(x, min_x, max_x), side(y, min_y, max_y)) {
+        (None, None) => return EDGE_INTERIOR,
+        (Some(false), Some(false)) => 0,
+        (None, Some(false)) => 1,
+        (Some(true), Some(false)) => 2,
+        (Some(true), None) => 3,
+        (Some(true), Some(true)) => 4,
+        (None, Some(true)) => 5,
+        (Some(false), Some(true)) => 6,
+        (Some(false), None) => 7,
+    };
+    anchor + 1
+}
+
+impl EditorCore {
+    /// Object snap targets: bounds of axis-aligned nodes and the oriented rects
+    /// of rotated nodes intersecting a rect
+    /// Only nodes the options accept as targets are included (see `SnapOptions::is_target`)
+    fn snap_targets(
+        &self,
+        min_x: f32,
+        min_y: f32,
+        max_x: f32,
+        max_y: f32,
+        options: &SnapOptions,
+    ) -> SnapTargets {
+        let mut aligned = Vec::new();
+        let mut oriented = Vec::new();
+        for handle in self.spatial_index.query_rect(min_x, min_y, max_x, max_y) {
+            if !options.is_target(handle, self.node_flags.get(&handle).copied().unwrap_or(0)) {
+                continue;
+            }
+            let Some(bounds) = self.spatial_index.get_bounds(handle) else {
+                continue;
+            };
+            match self.node_orientations.get(&handle) {
+                Some(&(width, height, rotation)) => oriented.push(OrientedRect {
+                    cx: (bounds.0 + bounds.2) / 2.0,
+                    cy: (bounds.1 + bounds.3) / 2.0,
+                    width,
+                    height,
+                    rotation,
+                }),
+                None => aligned.push(bounds),
+            }
+        }
+        (aligned, oriented)
+    }
+
+    /// Snap a resize's moving corner against nearby nodes (see `snap_resize`),
+    /// matching sibling sizes only when `match_sizes` is set
+    fn resize_snap(
+        &self,
+        anchor: (f32, f32),
+        corner: (f32, f32),
+        options: &SnapOptions,
+        match_sizes: bool,
+    ) -> ResizeSnapResult {
+        let (min_x, max_x) = (anchor.0.min(corner.0), anchor.0.max(corner.0));
+        let (min_y, max_y) = (anchor.1.min(corner.1), anchor.1.max(corner.1));
+        let reach = (max_x - min_x).max(max_y - min_y) + options.threshold;
+        let (aligned, oriented) =
+            self.snap_targets(min_x - reach, min_y - reach, max_x + reach, max_y + reach, options);
+
+        let mut candidates = snapping::point_candidates(corner, &aligned, &oriented, &self.virtual_targets, options);
+        if match_sizes {
+            let sizes: Vec<(f32, f32)> = aligned
+                .iter()
+                .map(|b| (b.2 - b.0, b.3 - b.1))
+                .chain(oriented.iter().map(|r| (r.width, r.height)))
+                .collect();
+            candidates.extend(snapping::size_candidates(anchor, corner, &sizes, options));
+        }
+        snapping::snap_resize(anchor, corner, &candidates)
+    }
+
+    /// Snap a point according to the given options
+    fn snap(&self, world_x: f32, world_y: f32, options: &SnapOptions) -> SnapResult {
+        let candidates = self.point_snap_candidates(world_x, world_y, options.threshold * 3.0, options);
+        let result = snapping::snap_point((world_x, world_y), &candidates, options.threshold);
+        match self.snap_decimals {
+            Some(decimals) => result.rounded(decimals),
+            None => result,
+        }
+    }
+
+    /// Snap candidates for a point, searching nodes within `query_radius`
+    fn point_snap_candidates(
+        &self,
+        world_x: f32,
+        world_y: f32,
+        query_radius: f32,
+        options: &SnapOptions,
+    ) -> Vec<snapping::SnapCandidate> {
+        let (aligned, oriented) = if options.objects {
+            self.snap_targets(
+                world_x - query_radius,
+                world_y - query_radius,
+                world_x + query_radius,
+                world_y + query_radius,
+                options,
+            )
+        } else {
+            (Vec::new(), Vec::new())
+        };
+
+        snapping::point_candidates((world_x, world_y), &aligned, &oriented, &self.virtual_targets, options)
+    }
+
+    /// Snap a box rigidly according to the given options
+    fn snap_box(&self, bounds: (f32, f32, f32, f32), options: &SnapOptions) -> BoundsSnapResult {
+        let (aligned, oriented) = if options.objects {
+            let t = options.threshold;
+            self.snap_targets(bounds.0 - t, bounds.1 - t, bounds.2 + t, bounds.3 + t, options)
+        } else {
+            (Vec::new(), Vec::new())
+        };
+
+        let result = snapping::snap_box(bounds, &aligned, &oriented, &self.virtual_targets, options);
+        match self.snap_decimals {
+            Some(decimals) => result.rounded(decimals),
+            None => result,
+        }
+    }
+
+    /// Interleave handles with their z-index bits (see `query_rect_with_z`)
+    fn with_z(&self, handles: Vec<u32>) -> Vec<i32> {
+        let mut result = Vec::with_capacity(handles.len() * 2);
+        for handle in handles {
+            let z = self.spatial_index.get_z_index(handle).unwrap_or(0.0);
+            result.push(handle as i32);
+            result.push(z.to_bits() as i32);
+        }
+        result
+    }
+
+    /// Check that every handle in a batch is known
+    fn check_handles(&self, handles: &[u32]) -> Result<(), EditorError> {
+        match handles.iter().find(|handle| !self.node_flags.contains_key(handle)) {
+            Some(&handle) => Err(EditorError::HandleNotFound(handle)),
+            None => Ok(()),
+        }
+    }
+
+    /// Record a node's flags, moving it out of or back into the grid when its
+    /// pinned bit changes
+    fn store_flags(&mut self, handle: u32, flags: u32) {
+        self.node_flags.insert(handle, flags);
+        self.spatial_index.set_pinned(handle, flags & FLAG_PINNED != 0);
+    }
+
+    /// Turn grid query results into a cull result: drop hidden nodes, then
+    /// append the non-hidden pinned nodes (which the grid never returns).
+    /// Returns the number of hidden nodes the query found.
+    fn finish_cull(&self, handles: &mut Vec<u32>) -> usize {
+        let candidates = handles.len();
+        handles.retain(|&handle| !self.has_flags(handle, FLAG_HIDDEN));
+        let hidden = candidates - handles.len();
+
+        handles.extend(
+            self.spatial_index
+                .pinned_handles()
+                .filter(|&handle| !self.has_flags(handle, FLAG_HIDDEN)),
+        );
+        hidden
+    }
+
+    /// Check whether any of the given flag bits are set on a node
+    fn has_flags(&self, handle: u32, mask: u32) -> bool {
+        self.node_flags
+            .get(&handle)
+            .is_some_and(|&flags| flags & mask != 0)
+    }
+
+    /// Stamp a node with the current generation and advance the counter
+    fn mark_modified(&mut self, handle: u32) {
+        self.removed_generations.remove(&handle);
+        self.node_generations.insert(handle, self.generation);
+        self.generation += 1;
+    }
+
+    /// Bounds of the visible nodes that are neither the moving node nor excluded,
+    /// followed by the virtual targets
+    fn reference_bounds(
+        &self,
+        moving_handle: u32,
+        visible_handles: &[u32],
+        exclude: Option<Vec<u32>>,
+    ) -> Vec<(f32, f32, f32, f32)> {
+        let exclude: FxHashSet<u32> = exclude.unwrap_or_default().into_iter().collect();
+
+        visible_handles
+            .iter()
+            .filter(|&&handle| handle != moving_handle && !exclude.contains(&handle))
+            .filter_map(|&handle| self.spatial_index.get_bounds(handle))
+            .chain(self.virtual_targets.iter().copied())
+            .collect()
+    }
+
+    /// Nearest of the edges selected by `edges` to `position`, strictly within threshold
+    fn nearest_edge(
+        &self,
+        position: f32,
+        handles: &[u32],
+        threshold: f32,
+        edges: impl Fn((f32, f32, f32, f32)) -> [f32; 2],
+    ) -> Option<f32> {
+        let mut nearest: Option<(f32, f32)> = None;
+
+        for &handle in handles {
+            if let Some(bounds) = self.spatial_index.get_bounds(handle) {
+                for edge in edges(bounds) {
+                    let distance = (position - edge).abs();
+                    if distance < threshold && nearest.is_none_or(|(_, best)| distance < best) {
+                        nearest = Some((edge, distance));
+                    }
+                }
+            }
+        }
+
+        nearest.map(|(edge, _)| edge)
+    }
+
+    /// Record a tombstone for a live node and advance the counter
+    fn mark_removed(&mut self, handle: u32) {
+        if self.node_generations.remove(&handle).is_some() {
+            self.removed_generations.insert(handle, self.generation);
+            self.generation += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_flags_many() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 0.0, 0.0, 10.0, 10.0, 0.0, FLAG_LOCKED);
+        core.upsert_node(2, 0.0, 0.0, 10.0, 10.0, 0.0, 0);
+
+        // Unknown handles reject the whole batch
+        let result = core.set_flags_many(vec![1, 2, 99], FLAG_HIDDEN, 0);
+        assert_eq!(result, Err(EditorError::HandleNotFound(99)));
+        assert_eq!(core.node_flags[&1], FLAG_LOCKED);
+        assert!(!core.node_flags.contains_key(&99));
+
+        // Clear is applied before set
+        core.set_flags_many(vec![1, 2], FLAG_HIDDEN, FLAG_LOCKED | FLAG_HIDDEN).unwrap();
+        assert_eq!(core.node_flags[&1], FLAG_HIDDEN);
+        assert_eq!(core.node_flags[&2], FLAG_HIDDEN);
+        assert!(core.hit_test_point(5.0, 5.0).is_empty());
+    }
+
+    #[test]
+    fn test_hit_test_top_tagged() {
+        const TAG_TEXT: u32 = 7;
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 0.0, 0.0, 100.0, 100.0, 1.0, 0);
+        core.upsert_node(2, 0.0, 0.0, 100.0, 100.0, 2.0, 0);
+        core.upsert_node(3, 0.0, 0.0, 100.0, 100.0, 3.0, FLAG_LOCKED);
+        core.upsert_node(4, 0.0, 0.0, 100.0, 100.0, 4.0, 0);
+        assert!(core.set_node_tag(1, TAG_TEXT));
+        assert!(core.set_node_tag(3, TAG_TEXT));
+        assert!(!core.set_node_tag(99, TAG_TEXT));
+
+        assert_eq!(core.hit_test_top_tagged(50.0, 50.0, TAG_TEXT, 0), Some(3));
+        assert_eq!(core.hit_test_top_tagged(50.0, 50.0, TAG_TEXT, FLAG_LOCKED), Some(1));
+        assert_eq!(core.hit_test_top_tagged(50.0, 50.0, 8, 0), None);
+        assert_eq!(core.hit_test_top_tagged(500.0, 50.0, TAG_TEXT, 0), None);
+
+        // Tags survive updates but not removal
+        core.upsert_node(1, 0.0, 0.0, 120.0, 120.0, 1.0, 0);
+        assert_eq!(core.get_node_tag(1), Some(TAG_TEXT));
+        core.remove_node(1);
+        assert_eq!(core.get_node_tag(1), None);
+    }
+
+    #[test]
+    fn test_select_all_visible() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 0.0, 0.0, 10.0, 10.0, 0.0, 0);
+        core.upsert_node(2, 0.0, 0.0, 10.0, 10.0, 0.0, FLAG_HIDDEN);
+        core.upsert_node(3, 0.0, 0.0, 10.0, 10.0, 0.0, FLAG_LOCKED);
+
+        let mut handles = core.select_all_visible();
+        handles.sort();
+        assert_eq!(handles, vec![1, 3]);
+    }
+
+    #[test]
+    fn test_prune_removed_before() {
+        let mut core = EditorCore::new(10);
+        for handle in 1..=3 {
+            core.upsert_node(handle, 0.0, 0.0, 10.0, 10.0, 0.0, 0);
+        }
+        core.remove_node(1);
+        let acknowledged = core.current_generation();
+        core.clear();
+
+        assert_eq!(core.prune_removed_before(acknowledged), 1);
+        let mut removed = core.nodes_removed_since(0);
+        removed.sort_unstable();
+        assert_eq!(removed, vec![2, 3]);
+
+        assert_eq!(core.prune_removed_before(core.current_generation()), 2);
+        assert!(core.nodes_removed_since(0).is_empty());
+    }
+
+    #[test]
+    fn test_generation_tracking() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 0.0, 0.0, 10.0, 10.0, 0.0, 0);
+        core.upsert_node(2, 0.0, 0.0, 10.0, 10.0, 0.0, 0);
+
+        let snapshot = core.current_generation();
+        core.upsert_node(2, 5.0, 5.0, 15.0, 15.0, 0.0, 0);
+        core.upsert_node(3, 0.0, 0.0, 10.0, 10.0, 0.0, 0);
+        core.remove_node(1);
+
+        let mut modified = core.nodes_modified_since(snapshot);
+        modified.sort();
+        assert_eq!(modified, vec![2, 3]);
+        assert_eq!(core.nodes_removed_since(snapshot), vec![1]);
+
+        // Re-inserting a removed node clears its tombstone
+        core.upsert_node(1, 0.0, 0.0, 10.0, 10.0, 0.0, 0);
+        assert!(core.nodes_removed_since(snapshot).is_empty());
+    }
+
+    #[test]
+    fn test_load_scene_packed() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(99, 0.0, 0.0, 10.0, 10.0, 0.0, 0);
+
+        let bits = f32::from_bits;
+        let scene = [
+            bits(1), 0.0, 0.0, 10.0, 10.0, 0.0, bits(0),
+            bits(2), 5.0, 5.0, 15.0, 15.0, 1.0, bits(FLAG_HIDDEN),
+        ];
+        assert_eq!(core.load_scene_packed(&scene), Ok(2));
+        assert_eq!(core.get_node_count(), 2);
+        assert_eq!(core.hit_test_point(7.0, 7.0), vec![1]);
+        assert!(core.nodes_removed_since(0).contains(&99));
+
+        // Truncated records are rejected without touching the scene
+        let error = core.load_scene_packed(&scene[..10]).unwrap_err();
+        assert_eq!(error, EditorError::PackedLength { len: 10, stride: 7 });
+        assert_eq!(error.to_string(), "packed data length 10 not a multiple of 7");
+        assert_eq!(core.get_node_count(), 2);
+    }
+
+    #[test]
+    fn test_batch_duplicate_handles() {
+        let mut core = EditorCore::new(10);
+
+        // Repeated handle in a packed load: last record wins, indexed once
+        let bits = f32::from_bits;
+        let scene = [
+            bits(5), 0.0, 0.0, 10.0, 10.0, 0.0, bits(0),
+            bits(5), 100.0, 100.0, 110.0, 110.0, 0.0, bits(0),
+        ];
+        assert_eq!(core.load_scene_packed(&scene), Ok(1));
+        assert_eq!(core.get_node_count(), 1);
+        assert!(core.hit_test_point(5.0, 5.0).is_empty());
+        assert_eq!(core.query_rect(0.0, 0.0, 1000.0, 1000.0), vec![5]);
+
+        // Repeated handle in a batch translate moves once
+        assert_eq!(core.translate_nodes(vec![5, 5], 10.0, 0.0), Ok(1));
+        assert_eq!(core.spatial_index.get_bounds(5), Some((110.0, 100.0, 120.0, 110.0)));
+
+        // Unknown handles reject the whole batch
+        assert_eq!(core.translate_nodes(vec![5, 6], 10.0, 0.0), Err(EditorError::HandleNotFound(6)));
+        assert_eq!(core.spatial_index.get_bounds(5), Some((110.0, 100.0, 120.0, 110.0)));
+    }
+
+    #[test]
+    fn test_export_all_round_trips() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 0.0, 0.0, 10.0, 10.0, 2.5, 0);
+        core.upsert_node(0xFFFF_FFF0, -50.0, 5.0, 15.0, 15.0, -1.0, FLAG_LOCKED);
+
+        let data = core.export_all();
+        assert_eq!(data.len(), 14);
+
+        let mut restored = EditorCore::new(10);
+        assert_eq!(restored.load_scene_packed(&data), Ok(2));
+        for handle in [1, 0xFFFF_FFF0] {
+            assert_eq!(
+                restored.spatial_index.get_bounds(handle),
+                core.spatial_index.get_bounds(handle)
+            );
+            assert_eq!(
+                restored.spatial_index.get_z_index(handle),
+                core.spatial_index.get_z_index(handle)
+            );
+            assert_eq!(restored.node_flags[&handle], core.node_flags[&handle]);
+        }
+    }
+
+    #[test]
+    fn test_overlapping_with() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 0.0, 0.0, 100.0, 100.0, 1.0, 0);
+        core.upsert_node(2, 50.0, 50.0, 150.0, 150.0, 3.0, 0);
+        core.upsert_node(3, 100.0, 0.0, 120.0, 20.0, 2.0, 0); // touches node 1's edge
+        core.upsert_node(4, 500.0, 500.0, 510.0, 510.0, 0.0, 0);
+
+        assert_eq!(core.overlapping_with(1, true), vec![2, 3]);
+        assert_eq!(core.overlapping_with(1, false), vec![2, 3, 1]);
+        assert!(core.overlapping_with(4, true).is_empty());
+        assert!(core.overlapping_with(99, false).is_empty());
+    }
+
+    #[test]
+    fn test_is_aligned() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 0.0, 0.0, 100.0, 50.0, 0.0, 0);
+        core.upsert_node(2, 0.0, 80.0, 60.0, 120.0, 0.0, 0);
+
+        assert!(core.is_aligned(1, 2, 0, 0.5));
+        assert!(!core.is_aligned(1, 2, 1, 0.5));
+        assert!(!core.is_aligned(1, 99, 0, 0.5));
+    }
+
+    #[test]
+    fn test_query_rect_both() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 10.0, 10.0, 20.0, 20.0, 1.0, 0); // contained
+        core.upsert_node(2, 90.0, 10.0, 120.0, 20.0, 2.0, 0); // crosses the right edge
+        core.upsert_node(3, 300.0, 10.0, 320.0, 20.0, 3.0, 0); // outside
+        core.upsert_node(u32::MAX, 0.0, 0.0, 100.0, 100.0, 0.0, 0); // exactly the rect
+
+        assert_eq!(core.query_rect_both(0.0, 0.0, 100.0, 100.0), vec![2, 1, 1, 0, -1, 0]);
+    }
+
+    #[test]
+    fn test_query_with_z() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 0.0, 0.0, 10.0, 10.0, 2.5, 0);
+        core.upsert_node(2, 5.0, 5.0, 15.0, 15.0, -1.0, 0);
+        core.upsert_node(3, 6.0, 6.0, 8.0, 8.0, 9.0, FLAG_LOCKED);
+
+        let decode = |pairs: Vec<i32>| -> Vec<(u32, f32)> {
+            pairs.chunks(2).map(|p| (p[0] as u32, f32::from_bits(p[1] as u32))).collect()
+        };
+
+        let mut hits = decode(core.query_rect_with_z(0.0, 0.0, 20.0, 20.0));
+        hits.sort_by_key(|&(handle, _)| handle);
+        assert_eq!(hits, vec![(1, 2.5), (2, -1.0), (3, 9.0)]);
+
+        // Same order and filtering as hit_test_point
+        assert_eq!(decode(core.hit_test_point_with_z(7.0, 7.0)), vec![(1, 2.5), (2, -1.0)]);
+    }
+
+    #[test]
+    fn test_intersects() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 0.0, 0.0, 10.0, 10.0, 0.0, 0);
+        core.upsert_node(2, 10.0, 5.0, 20.0, 15.0, 0.0, 0);
+        core.upsert_node(3, 30.0, 0.0, 40.0, 10.0, 0.0, 0);
+
+        assert!(core.intersects(1, 2)); // touching edges
+        assert!(core.intersects(2, 1));
+        assert!(!core.intersects(1, 3));
+        assert!(!core.intersects(1, 99));
+    }
+
+    #[test]
+    fn test_find_free_rect_order() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 0.0, 0.0, 100.0, 100.0, 0.0, 0); // blocks the desired spot
+        core.upsert_node(2, -150.0, -150.0, 50.0, -50.0, 0.0, 0); // blocks ring 1's first two
+
+        // Ring 1 starts top-left, then walks the top row; (100, -100) only touches node 1
+        let found = core.find_free_rect(100.0, 100.0, 0.0, 0.0, 100.0, 3);
+        assert_eq!(found, Some(vec![100.0, -100.0, 200.0, 0.0]));
+
+        assert_eq!(core.find_free_rect(100.0, 100.0, 0.0, 0.0, 100.0, 0), None);
+        assert_eq!(core.find_free_rect(10.0, 10.0, 500.0, 500.0, 20.0, 0), Some(vec![500.0, 500.0, 510.0, 510.0]));
+
+        let ring: Vec<(i32, i32)> = ring_offsets(1).collect();
+        assert_eq!(ring, vec![(-1, -1), (0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0)]);
+        assert_eq!(ring_offsets(2).count(), 16);
+        assert_eq!(ring_offsets(0).collect::<Vec<_>>(), vec![(0, 0)]);
+    }
+
+    #[test]
+    fn test_query_rect_by_distance() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(5, 40.0, 0.0, 60.0, 20.0, 0.0, 0); // center (50, 10): 40 away
+        core.upsert_node(3, 40.0, 40.0, 60.0, 60.0, 0.0, 0); // centered
+        core.upsert_node(9, 0.0, 40.0, 20.0, 60.0, 0.0, 0); // center (10, 50): 40 away
+        core.upsert_node(1, 65.0, 40.0, 75.0, 60.0, 0.0, 0); // center (70, 50): 20 away
+        core.upsert_node(7, 500.0, 500.0, 510.0, 510.0, 0.0, 0); // outside
+
+        assert_eq!(core.query_rect_by_distance(0.0, 0.0, 100.0, 100.0), vec![3, 1, 5, 9]);
+    }
+
+    #[test]
+    fn test_ensure_content_visible() {
+        let mut core = EditorCore::new(10);
+        assert!(!core.ensure_content_visible(0.5));
+        core.set_camera(1.0, 0.0, 0.0, 800.0, 600.0, 1.0, None, None, None, None);
+
+        // Content entirely below-left of the viewport
+        core.upsert_node(1, -2000.0, 1000.0, -1900.0, 1100.0, 0.0, 0);
+        core.upsert_node(2, -1850.0, 1050.0, -1800.0, 1200.0, 0.0, 0);
+        assert_eq!(core.content_bounds(), Some(vec![-2000.0, 1000.0, -1800.0, 1200.0]));
+        assert!(!core.is_rect_visible(-2000.0, 1000.0, -1800.0, 1200.0));
+
+        assert!(core.ensure_content_visible(0.5));
+        assert!(core.is_rect_visible(-2000.0, 1000.0, -1800.0, 1200.0));
+        assert_eq!(core.get_zoom(), 1.0);
+        assert_eq!(core.cull_visible().len(), 2);
+
+        // Already satisfied: no further change
+        assert!(!core.ensure_content_visible(0.5));
+    }
+
+    #[test]
+    fn test_selection_extent() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 0.0, 0.0, 10.0, 10.0, 0.0, 0);
+        core.upsert_node(2, 50.0, -20.0, 70.0, 5.0, 0.0, 0);
+
+        // Unknown and repeated handles don't count
+        assert_eq!(core.selection_extent(vec![1, 99, 2, 1]), vec![0.0, -20.0, 70.0, 10.0, 2.0, 600.0]);
+        assert_eq!(core.selection_extent(vec![99]), vec![0.0; 6]);
+    }
+
+    #[test]
+    fn test_groups_in_rect() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 0.0, 0.0, 10.0, 10.0, 0.0, 0);
+        core.upsert_node(2, 20.0, 0.0, 30.0, 10.0, 0.0, 0);
+        core.upsert_node(3, 40.0, 0.0, 50.0, 10.0, 0.0, 0);
+        core.upsert_node(4, 60.0, 0.0, 70.0, 10.0, 0.0, 0); // ungrouped
+        core.upsert_node(5, 500.0, 0.0, 510.0, 10.0, 0.0, 0);
+        core.set_node_group(1, 7);
+        core.set_node_group(2, 7);
+        core.set_node_group(3, 3);
+        core.set_node_group(5, 9);
+        assert!(!core.set_node_group(99, 1));
+        assert_eq!(core.get_node_group(1), 7);
+
+        assert_eq!(core.groups_in_rect(0.0, 0.0, 100.0, 10.0, None), vec![3, 7]);
+        assert_eq!(core.groups_in_rect(0.0, 0.0, 100.0, 10.0, Some(true)), vec![0, 3, 7]);
+
+        core.remove_node(3);
+        assert_eq!(core.groups_in_rect(0.0, 0.0, 100.0, 10.0, None), vec![7]);
+    }
+
+    #[test]
+    fn test_query_rect_created_after() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 0.0, 0.0, 10.0, 10.0, 0.0, 0);
+
+        let seq = core.current_seq();
+        core.upsert_node(2, 20.0, 0.0, 30.0, 10.0, 0.0, 0);
+        core.upsert_node(3, 40.0, 0.0, 50.0, 10.0, 0.0, FLAG_HIDDEN);
+        // Updating an existing node keeps its original sequence
+        core.upsert_node(1, 0.0, 0.0, 12.0, 10.0, 0.0, 0);
+
+        assert_eq!(core.current_seq(), seq + 2);
+        assert_eq!(core.query_rect_created_after(0.0, 0.0, 100.0, 100.0, seq), vec![2]);
+    }
+
+    #[test]
+    fn test_cull_at_extreme_zoom_out() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 0.0, 0.0, 10.0, 10.0, 0.0, 0);
+        core.upsert_node(2, 5.0e5, 5.0e5, 5.0e5 + 10.0, 5.0e5 + 10.0, 0.0, 0);
+
+        // Visible bounds span ~1e9 world units: billions of cells
+        core.set_zoom_limits(1.0e-6, 256.0);
+        core.set_camera(1.0e-6, 0.0, 0.0, 800.0, 600.0, 1.0, None, None, None, None);
+
+        let mut visible = core.cull_visible();
+        visible.sort();
+        assert_eq!(visible, vec![1, 2]);
+    }
+
+    #[test]
+    fn test_cull_visible_with_stats() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 0.0, 0.0, 10.0, 10.0, 0.0, 0);
+        core.upsert_node(2, 20.0, 0.0, 30.0, 10.0, 0.0, FLAG_HIDDEN);
+        core.upsert_node(3, 40.0, 0.0, 50.0, 10.0, 0.0, FLAG_HIDDEN | FLAG_LOCKED);
+        core.upsert_node(4, 5000.0, 0.0, 5010.0, 10.0, 0.0, FLAG_HIDDEN); // off-screen
+
+        let result = core.cull_visible_with_stats();
+        assert_eq!(result.handles(), core.cull_visible());
+        assert_eq!(result.visible_count(), 1);
+        assert_eq!(result.hidden_count(), 2);
+    }
+
+    #[test]
+    fn test_cull_diff() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 0.0, 0.0, 10.0, 10.0, 0.0, 0);
+        core.upsert_node(2, 300.0, 0.0, 310.0, 10.0, 0.0, 0);
+        core.upsert_node(3, 1000.0, 0.0, 1010.0, 10.0, 0.0, 0);
+        core.set_camera(1.0, 0.0, 0.0, 800.0, 600.0, 1.0, None, None, None, None);
+
+        let diff = core.cull_diff();
+        assert_eq!((diff.entered(), diff.left()), (vec![1, 2], vec![]));
+
+        // Pan right: 1 leaves, 3 enters, 2 stays
+        core.set_camera(1.0, 600.0, 0.0, 800.0, 600.0, 1.0, None, None, None, None);
+        let diff = core.cull_diff();
+        assert_eq!((diff.entered(), diff.left()), (vec![3], vec![1]));
+
+        let diff = core.cull_diff();
+        assert!(diff.entered().is_empty() && diff.left().is_empty());
+    }
+
+    #[test]
+    fn test_is_rect_visible() {
+        let mut core = EditorCore::new(10);
+        // Visible world bounds: (-400, -300) to (400, 300)
+        core.set_camera(1.0, 0.0, 0.0, 800.0, 600.0, 1.0, None, None, None, None);
+
+        assert!(core.is_rect_visible(-10.0, -10.0, 10.0, 10.0));
+        // Straddles the right edge
+        assert!(core.is_rect_visible(390.0, 0.0, 450.0, 20.0));
+        assert!(!core.is_rect_visible(401.0, 0.0, 450.0, 20.0));
+        assert!(!core.is_rect_visible(-10.0, -500.0, 10.0, -301.0));
+    }
+
+    #[test]
+    fn test_cull_empty_index() {
+        let mut core = EditorCore::new(0);
+        core.set_zoom_limits(1.0e-6, 256.0);
+        core.set_camera(1.0e-6, 0.0, 0.0, 800.0, 600.0, 1.0, None, None, None, None);
+
+        assert!(core.cull_visible().is_empty());
+        assert_eq!(core.cull_visible_buffered(), 0);
+        assert!(core.query_rect(-1.0e3, -1.0e3, 1.0e3, 1.0e3).is_empty());
+    }
+
+    #[test]
+    fn test_length_conversion_dpr() {
+        let mut core = EditorCore::new(10);
+        core.set_camera(2.0, 0.0, 0.0, 800.0, 600.0, 1.5, None, None, None, None);
+
+        assert_eq!(core.world_length_to_screen(10.0, None), 20.0);
+        assert_eq!(core.world_length_to_screen(10.0, Some(true)), 30.0);
+        assert_eq!(core.screen_length_to_world(30.0, Some(true)), 10.0);
+        assert_eq!(core.screen_length_to_world(20.0, Some(false)), 10.0);
+    }
+
+    #[test]
+    fn test_camera_getters() {
+        let mut core = EditorCore::new(10);
+        core.set_camera(2.5, -40.0, 75.0, 1024.0, 768.0, 2.0, None, None, None, None);
+
+        assert_eq!(core.get_zoom(), 2.5);
+        assert_eq!(core.get_pan_x(), -40.0);
+        assert_eq!(core.get_pan_y(), 75.0);
+        assert_eq!(core.get_camera(), vec![2.5, -40.0, 75.0, 1024.0, 768.0, 2.0]);
+    }
+
+    #[test]
+    fn test_nearest_edge() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 0.0, 0.0, 100.0, 50.0, 0.0, 0);
+        core.upsert_node(2, 104.0, 200.0, 150.0, 260.0, 0.0, 0);
+
+        assert_eq!(core.nearest_edge_x(103.0, vec![1, 2], 5.0), Some(104.0));
+        assert_eq!(core.nearest_edge_x(101.0, vec![1, 2], 5.0), Some(100.0));
+        assert_eq!(core.nearest_edge_x(75.0, vec![1, 2], 5.0), None);
+        assert_eq!(core.nearest_edge_y(57.0, vec![1, 2], 10.0), Some(50.0));
+        assert_eq!(core.nearest_edge_y(57.0, vec![2], 10.0), None);
+    }
+
+    #[test]
+    fn test_nearest_edge_distance() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 100.0, 0.0, 200.0, 50.0, 0.0, 0);
+        core.upsert_node(2, 0.0, 90.0, 85.0, 95.0, 0.0, FLAG_HIDDEN);
+
+        // Left edge 10 to the right; bottom edge 20 above
+        assert_eq!(core.nearest_edge_distance(90.0, 70.0, 25.0), vec![10.0, -20.0]);
+
+        // The hidden node's nearer edges don't count
+        assert_eq!(core.nearest_edge_distance(90.0, 80.0, 32.0), vec![10.0, -30.0]);
+
+        // Nothing within the radius
+        assert_eq!(core.nearest_edge_distance(90.0, 80.0, 15.0), vec![f32::MAX, f32::MAX]);
+        assert_eq!(core.nearest_edge_distance(-500.0, -500.0, 15.0), vec![f32::MAX, f32::MAX]);
+    }
+
+    #[test]
+    fn test_snap_point_nearest_candidate_wins() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 103.0, -50.0, 200.0, -10.0, 0.0, 0); // left edge 3 away
+        core.upsert_node(2, 101.0, 10.0, 180.0, 50.0, 0.0, 0); // left edge 1 away
+
+        // Both edges are within threshold: the nearer wins, whatever the order
+        let result = core.snap_point(100.0, 0.0, 5.0, 0.0, false, true, None, None, None);
+        assert_eq!(result.x(), 101.0);
+        assert_eq!(result.guide_count(), 2);
+
+        // A grid line closer than any edge now beats the edges
+        let result = core.snap_point(100.2, 0.0, 5.0, 50.0, true, true, None, None, None);
+        assert_eq!((result.x(), result.y()), (100.0, 0.0));
+        assert_eq!(result.guide_count(), 4); // two edges, grid x, grid y
+    }
+
+    #[test]
+    fn test_snap_point_excludes_dragged_node() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 100.0, 100.0, 200.0, 200.0, 0.0, 0);
+        core.upsert_node(2, 300.0, 0.0, 400.0, 50.0, 0.0, 0);
+
+        // Pointer just inside node 1's own left edge
+        assert_eq!(core.snap_point(102.0, 150.0, 5.0, 0.0, false, true, None, None, None).x(), 100.0);
+
+        let result = core.snap_point(102.0, 150.0, 5.0, 0.0, false, true, Some(1), None, None);
+        assert!(!result.snapped());
+        assert_eq!((result.x(), result.y()), (102.0, 150.0));
+
+        // Other nodes still snap
+        let result = core.snap_point(297.0, 40.0, 5.0, 0.0, false, true, Some(1), None, None);
+        assert_eq!(result.x(), 300.0);
+    }
+
+    #[test]
+    fn test_snap_point_rotated_node_edges() {
+        // 100x100 square rotated 45 degrees about (500, 500): AABB is +-d
+        let d = 50.0 * std::f32::consts::SQRT_2;
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 500.0 - d, 500.0 - d, 500.0 + d, 500.0 + d, 0.0, 0);
+
+        // Unrotated, a point near the AABB's right side snaps to it
+        let result = core.snap_point(500.0 + d - 2.0, 500.0 + d / 2.0, 5.0, 0.0, false, true, None, None, None);
+        assert!((result.x() - (500.0 + d)).abs() < 1e-3);
+
+        assert!(core.set_node_rotation(1, 100.0, 100.0, std::f32::consts::FRAC_PI_4));
+        assert!(!core.set_node_rotation(99, 100.0, 100.0, 1.0));
+        assert_eq!(core.get_node_rotation(1), std::f32::consts::FRAC_PI_4);
+
+        // Rotated, the bounding box edge is gone; the visual edge at that height snaps
+        let result = core.snap_point(500.0 + d - 2.0, 500.0 + d / 2.0, 5.0, 0.0, false, true, None, None, None);
+        assert!(!result.snapped());
+        let result = core.snap_point(500.0 + d / 2.0 + 2.0, 500.0 + d / 2.0, 5.0, 0.0, false, true, None, None, None);
+        assert!((result.x() - (500.0 + d / 2.0)).abs() < 1e-3);
+
+        // Rotation 0 restores bounds snapping
+        core.set_node_rotation(1, 100.0, 100.0, 0.0);
+        let result = core.snap_point(500.0 + d - 2.0, 500.0 + d / 2.0, 5.0, 0.0, false, true, None, None, None);
+        assert!((result.x() - (500.0 + d)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_snap_bounds_and_sizes_rotated_node() {
+        // 100x100 square rotated 45 degrees about (300, 300): AABB is +-d
+        let d = 50.0 * std::f32::consts::SQRT_2;
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 300.0 - d, 300.0 - d, 300.0 + d, 300.0 + d, 0.0, 0);
+        core.set_node_rotation(1, 100.0, 100.0, std::f32::consts::FRAC_PI_4);
+
+        // A box beside the AABB's right side, level with the rotated node's lower
+        // half, no longer snaps to it; level with its right corner, it does
+        let x = 300.0 + d + 2.0;
+        let y = 300.0 + d / 2.0;
+        let result = core.snap_bounds(x, y - 10.0, x + 50.0, y + 10.0, 5.0, 0.0, false, true, None, None, None, None);
+        assert!(!result.snapped());
+        let result = core.snap_bounds(x, 290.0, x + 50.0, 310.0, 5.0, 0.0, false, true, None, None, None, None);
+        assert!((result.min_x() - (300.0 + d)).abs() < 1e-3);
+
+        // Size matching uses the unrotated width, not the AABB's
+        let result = core.snap_resize(0.0, 0.0, 98.0, 320.0, 5.0, None, None, None);
+        assert!(result.width_matched());
+        assert_eq!(result.matched_width(), 100.0);
+        let result = core.snap_resize(0.0, 0.0, 2.0 * d - 2.0, 320.0, 5.0, None, None, None);
+        assert!(!result.width_matched());
+    }
+
+    #[test]
+    fn test_node_rotation_cleared_on_resize() {
+        let d = 50.0 * std::f32::consts::SQRT_2;
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 500.0 - d, 500.0 - d, 500.0 + d, 500.0 + d, 0.0, 0);
+        core.set_node_rotation(1, 100.0, 100.0, std::f32::consts::FRAC_PI_4);
+
+        // Moving keeps the rotation, whether by translate or by upsert
+        core.translate_node(1, 10.0, 0.0);
+        core.upsert_node(1, 490.0 - d, 500.0 - d, 490.0 + d, 500.0 + d, 0.0, 0);
+        assert_eq!(core.get_node_rotation(1), std::f32::consts::FRAC_PI_4);
+        assert!(!core.handle_contains_point(1, 490.0 + d - 1.0, 500.0 - d + 1.0));
+
+        // Resizing drops it, so the new bounds are used as-is
+        core.upsert_node(1, 0.0, 0.0, 300.0, 300.0, 0.0, 0);
+        assert_eq!(core.get_node_rotation(1), 0.0);
+        assert!(core.handle_contains_point(1, 299.0, 1.0));
+        let result = core.snap_point(297.0, 150.0, 5.0, 0.0, false, true, None, None, None);
+        assert_eq!(result.x(), 300.0);
+    }
+
+    #[test]
+    fn test_snap_targets_scoped_by_flags() {
+        const FRAME: u32 = 0x10; // app-defined flag bit
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 100.0, 0.0, 200.0, 50.0, 0.0, 0); // plain shape
+        core.upsert_node(2, 0.0, 100.0, 50.0, 200.0, 0.0, FRAME);
+
+        // Unscoped, any node attracts
+        assert_eq!(core.snap_point(102.0, 20.0, 5.0, 0.0, false, true, None, None, None).x(), 100.0);
+
+        // Frames only: the shape no longer attracts, the frame still does
+        let scoped = |x, y| core.snap_point(x, y, 5.0, 0.0, false, true, None, Some(FRAME), Some(FRAME));
+        assert!(!scoped(102.0, 20.0).snapped());
+        assert_eq!(scoped(52.0, 98.0).x(), 50.0);
+        assert_eq!(scoped(52.0, 98.0).y(), 100.0);
+
+        // Mask with match 0 selects the complement
+        let shapes = core.snap_point(52.0, 98.0, 5.0, 0.0, false, true, None, Some(FRAME), Some(0));
+        assert!(!shapes.snapped());
+
+        // A match without a mask scopes nothing: every node still attracts
+        let unmasked = |x, y| core.snap_point(x, y, 5.0, 0.0, false, true, None, None, Some(FRAME));
+        assert_eq!(unmasked(102.0, 20.0).x(), 100.0);
+        assert_eq!(unmasked(52.0, 98.0).y(), 100.0);
+    }
+
+    #[test]
+    fn test_snap_resize_anchor_corners() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 100.0, 100.0, 200.0, 200.0, 0.0, 0); // box being resized
+        core.set_virtual_targets(&[50.0, 50.0, 250.0, 250.0]).unwrap(); // edges at 50 / 250
+
+        let snap = |anchor, x, y| {
+            core.snap_resize_anchor(100.0, 100.0, 200.0, 200.0, anchor, x, y, 5.0, Some(1), None, None)
+        };
+        let cases = [
+            (0, (52.0, 53.0), (50.0, 50.0), (50.0, 50.0, 200.0, 200.0)), // top-left
+            (2, (248.0, 52.0), (250.0, 50.0), (100.0, 50.0, 250.0, 200.0)), // top-right
+            (4, (247.0, 248.0), (250.0, 250.0), (100.0, 100.0, 250.0, 250.0)), // bottom-right
+            (6, (53.0, 251.0), (50.0, 250.0), (50.0, 100.0, 200.0, 250.0)), // bottom-left
+        ];
+        for (anchor, pointer, snapped, bounds) in cases {
+            let result = snap(anchor, pointer.0, pointer.1).unwrap();
+            assert!(result.snapped());
+            assert_eq!((result.x(), result.y()), snapped, "anchor {anchor}");
+            assert_eq!((result.min_x(), result.min_y(), result.max_x(), result.max_y()), bounds, "anchor {anchor}");
+        }
+
+        // Top edge: only y moves; x stays at the edge midpoint
+        let result = snap(1, 170.0, 52.0).unwrap();
+        assert_eq!((result.x(), result.y()), (150.0, 50.0));
+        assert_eq!((result.min_x(), result.min_y(), result.max_x(), result.max_y()), (100.0, 50.0, 200.0, 200.0));
+        assert_eq!(result.matched_width(), 100.0);
+
+        // Center moves the box, snapping its center
+        let result = snap(8, 151.0, 203.0).unwrap();
+        assert_eq!((result.x(), result.y()), (150.0, 203.0));
+        assert_eq!((result.min_x(), result.min_y(), result.max_x(), result.max_y()), (100.0, 153.0, 200.0, 253.0));
+
+        assert!(snap(9, 0.0, 0.0).is_none());
+    }
+
+    #[test]
+    fn test_snap_resize_matches_sibling_width() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 0.0, 0.0, 120.0, 40.0, 0.0, 0); // resized box
+        core.upsert_node(2, 150.0, 100.0, 250.0, 160.0, 0.0, 0); // sibling: 100 x 60
+
+        // Dragging the bottom-right corner from (0, 0): width 97 -> 100
+        let result = core.snap_resize(0.0, 0.0, 97.0, 40.0, 5.0, Some(1), None, None);
+        assert!(result.width_matched());
+        assert_eq!((result.x(), result.matched_width()), (100.0, 100.0));
+        assert!(!result.height_matched());
+        assert_eq!(result.y(), 40.0);
+
+        // Far from any width or edge: no snap
+        let result = core.snap_resize(0.0, 0.0, 130.0, 40.0, 5.0, Some(1), None, None);
+        assert!(!result.snapped());
+        assert_eq!(result.matched_width(), 130.0);
+    }
+
+    #[test]
+    fn test_snap_point_flat() {
+        let core = EditorCore::new(10);
+        let flat = core.snap_point_flat(103.0, 47.0, 5.0, 50.0, true, false, None, None, None);
+        assert_eq!(flat, vec![1.0, 100.0, 50.0, 2.0, -3.0, 3.0]);
+
+        let flat = core.snap_point_flat(120.0, 75.0, 5.0, 50.0, true, false, None, None, None);
+        assert_eq!(flat, vec![0.0, 120.0, 75.0, 0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_snap_rounding() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 99.99998, 0.0, 200.0, 50.0, 0.0, 0); // edge carrying f32 noise
+
+        assert_eq!(core.snap_point(102.0, 10.0, 5.0, 0.0, false, true, None, None, None).x(), 99.99998);
+
+        core.set_snap_rounding(Some(0));
+        let result = core.snap_point(102.0, 10.3, 5.0, 0.0, false, true, None, None, None);
+        assert_eq!((result.x(), result.y()), (100.0, 10.0));
+        assert_eq!(core.snap_bounds(101.0, 10.0, 121.0, 30.0, 5.0, 0.0, false, true, None, None, None, None).min_x(), 100.0);
+
+        // The debug dump reports the same rounded winner
+        let debug = core.snap_debug(102.0, 10.3, 5.0, 0.0, false, true);
+        let winner_x = debug.chunks(6).find(|c| c[0] == 0.0 && c[5] == 1.0).unwrap();
+        assert_eq!((winner_x[1], winner_x[4]), (100.0, -2.0));
+
+        // Huge decimal counts are capped rather than overflowing the scale factor
+        core.set_snap_rounding(Some(u32::MAX));
+        assert_eq!(core.snap_point(102.0, 10.0, 5.0, 0.0, false, true, None, None, None).x(), 99.99998);
+        core.set_snap_rounding(Some(400));
+        assert_eq!(core.snap_point(102.0, 10.0, 5.0, 0.0, false, true, None, None, None).x(), 99.99998);
+
+        core.set_snap_rounding(None);
+        assert_eq!(core.snap_point(102.0, 10.0, 5.0, 0.0, false, true, None, None, None).x(), 99.99998);
+    }
+
+    #[test]
+    fn test_virtual_targets() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 0.0, 0.0, 10.0, 10.0, 0.0, 0);
+        assert_eq!(
+            core.set_virtual_targets(&[200.0, 0.0, 300.0, 500.0, 1.0]),
+            Err(EditorError::PackedLength { len: 5, stride: 4 })
+        );
+        core.set_virtual_targets(&[200.0, 0.0, 300.0, 500.0]).unwrap();
+
+        // Snaps to the virtual rect's left edge
+        let result = core.snap_point(198.0, 250.0, 5.0, 0.0, false, true, None, None, None);
+        assert!(result.snapped());
+        assert_eq!(result.x(), 200.0);
+
+        // Participates in alignment guides (shared top edge)
+        let guides = core.calculate_alignment_guides(1, vec![], 1.0, None, None);
+        assert_eq!(guides.len(), 1);
+        assert_eq!(guides[0].position(), 0.0);
+
+        core.set_virtual_targets(&[]).unwrap();
+        assert!(!core.snap_point(198.0, 250.0, 5.0, 0.0, false, true, None, None, None).snapped());
+    }
+
+    #[test]
+    fn test_snap_debug_marks_winners() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 100.0, 100.0, 200.0, 200.0, 0.0, 0);
+
+        let debug = core.snap_debug(103.0, 149.0, 5.0, 10.0, true, true);
+        let rows: Vec<&[f32]> = debug.chunks_exact(6).collect();
+        let result = core.snap_point(103.0, 149.0, 5.0, 10.0, true, true, None, None, None);
+
+        // One winner per axis, matching snap_point
+        let winners: Vec<&&[f32]> = rows.iter().filter(|r| r[5] == 1.0).collect();
+        assert_eq!(winners.len(), 2);
+        assert!(winners.iter().any(|r| r[0] == 0.0 && r[1] == result.x() && r[4] == -3.0));
+        assert!(winners.iter().any(|r| r[0] == 1.0 && r[1] == result.y() && r[4] == 1.0));
+
+        // Losing candidates are still reported (grid line x = 100 ties the edge)
+        assert!(rows.len() > 2);
+    }
+
+    #[test]
+    fn test_snap_point_grid_per_axis() {
+        let core = EditorCore::new(10);
+
+        let result = core.snap_point_axes(103.0, 47.0, 5.0, 50.0, true, false, false);
+        assert_eq!((result.x(), result.y()), (100.0, 47.0));
+
+        let result = core.snap_point_axes(103.0, 47.0, 5.0, 50.0, false, true, false);
+        assert_eq!((result.x(), result.y()), (103.0, 50.0));
+    }
+
+    #[test]
+    fn test_snap_point_axis_locked() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 100.0, 100.0, 200.0, 200.0, 0.0, 0);
+
+        // x locked: 102.3 would snap to the left edge, but passes through exactly
+        let result = core.snap_point_locked(102.3, 198.0, 5.0, 0.0, false, true, 1);
+        assert_eq!(result.x(), 102.3);
+        assert_eq!(result.y(), 200.0);
+
+        let result = core.snap_point_locked(102.3, 198.0, 5.0, 0.0, false, true, 2);
+        assert_eq!((result.x(), result.y()), (100.0, 198.0));
+    }
+
+    #[test]
+    fn test_snap_bounds_is_rigid() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 200.0, 0.0, 300.0, 100.0, 0.0, 0);
+
+        // Right edge 197 snaps to the node's left edge 200, top 3 snaps to 0
+        let result = core.snap_bounds(100.0, 3.0, 197.0, 53.0, 5.0, 0.0, false, true, None, None, None, None);
+        assert!(result.snapped());
+        assert_eq!((result.dx(), result.dy()), (3.0, -3.0));
+        assert_eq!((result.min_x(), result.min_y()), (103.0, 0.0));
+        assert_eq!((result.max_x(), result.max_y()), (200.0, 50.0));
+        assert_eq!((result.width(), result.height()), (97.0, 50.0));
+
+        let result = core.snap_bounds(0.0, 500.0, 50.0, 550.0, 5.0, 0.0, false, true, None, None, None, None);
+        assert!(!result.snapped());
+        assert_eq!((result.min_x(), result.max_x()), (0.0, 50.0));
+    }
+
+    #[test]
+    fn test_snap_bounds_threshold_scales_with_size() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 1000.0, 0.0, 1100.0, 100.0, 0.0, 0);
+
+        // Both boxes end 10 units left of the node; base 4 + 0.1 * min side
+        let small = core.snap_bounds(980.0, 50.0, 990.0, 60.0, 4.0, 0.0, false, true, Some(0.1), None, None, None);
+        assert_eq!(small.dx(), 0.0);
+
+        let large = core.snap_bounds(790.0, 50.0, 990.0, 250.0, 4.0, 0.0, false, true, Some(0.1), None, None, None);
+        assert_eq!(large.dx(), 10.0);
+
+        // Capped threshold loses the pull again; no factor keeps the flat threshold
+        let capped = core.snap_bounds(790.0, 50.0, 990.0, 250.0, 4.0, 0.0, false, true, Some(0.1), Some(8.0), None, None);
+        assert_eq!(capped.dx(), 0.0);
+        let flat = core.snap_bounds(790.0, 50.0, 990.0, 250.0, 4.0, 0.0, false, true, None, None, None, None);
+        assert_eq!(flat.dx(), 0.0);
+    }
+
+    #[test]
+    fn test_set_z_index() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 0.0, 0.0, 600.0, 600.0, 1.0, 0);
+        core.upsert_node(2, 100.0, 100.0, 200.0, 200.0, 2.0, 0);
+        let cells = core.cell_count_for(1);
+        let memory = core.spatial_index.estimated_memory_bytes();
+
+        assert_eq!(core.hit_test_point(150.0, 150.0), vec![2, 1]);
+        let generation = core.current_generation();
+        assert!(core.set_z_index(1, 3.0));
+        assert_eq!(core.hit_test_point(150.0, 150.0), vec![1, 2]);
+
+        // Same cells and bounds; only z changed
+        assert_eq!(core.cell_count_for(1), cells);
+        assert_eq!(core.spatial_index.estimated_memory_bytes(), memory);
+        assert_eq!(core.query_rect(550.0, 550.0, 560.0, 560.0), vec![1]);
+        assert_eq!(core.spatial_index.get_z_index(1), Some(3.0));
+        assert_eq!(core.nodes_modified_since(generation), vec![1]);
+
+        assert!(!core.set_z_index(99, 0.0));
+    }
+
+    #[test]
+    fn test_handle_contains_point() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 0.0, 0.0, 10.0, 10.0, 0.0, FLAG_LOCKED);
+        assert!(core.handle_contains_point(1, 5.0, 5.0));
+        assert!(core.handle_contains_point(1, 10.0, 0.0)); // edges inclusive
+        assert!(!core.handle_contains_point(1, 10.5, 5.0));
+        assert!(!core.handle_contains_point(99, 5.0, 5.0));
+
+        // 100x20 bar rotated 45 degrees about (500, 500): the AABB corner is empty
+        let d = 60.0 * std::f32::consts::FRAC_1_SQRT_2;
+        core.upsert_node(2, 500.0 - d, 500.0 - d, 500.0 + d, 500.0 + d, 0.0, 0);
+        assert!(core.set_node_rotation(2, 100.0, 20.0, std::f32::consts::FRAC_PI_4));
+        assert!(core.handle_contains_point(2, 530.0, 530.0));
+        assert!(!core.handle_contains_point(2, 500.0 + d - 1.0, 500.0 - d + 1.0));
+    }
+
+    #[test]
+    fn test_set_z_indices() {
+        let mut core = EditorCore::new(10);
+        for handle in 1..=3 {
+            core.upsert_node(handle, 0.0, 0.0, 10.0, 10.0, handle as f32, 0);
+        }
+        assert_eq!(core.hit_test_point(5.0, 5.0), vec![3, 2, 1]);
+
+        // Reverse the stack; 99 is skipped and the repeated 1 keeps its last value
+        let updated = core.set_z_indices(vec![1, 2, 3, 99, 1], vec![0.0, 1.0, 0.0, 5.0, 2.0]);
+        assert_eq!(updated, Ok(3));
+        assert_eq!(core.hit_test_point(5.0, 5.0), vec![1, 2, 3]);
+
+        assert_eq!(
+            core.set_z_indices(vec![1, 2], vec![9.0]),
+            Err(EditorError::LengthMismatch { handles: 2, values: 1 })
+        );
+        assert_eq!(core.spatial_index.get_z_index(1), Some(2.0));
+    }
+
+    #[test]
+    fn test_all_handles() {
+        let mut core = EditorCore::new(10);
+        for handle in [7, 3, 0xFFFF_FFF0, 12] {
+            core.upsert_node(handle, 0.0, 0.0, 10.0, 10.0, 0.0, 0);
+        }
+        core.remove_node(12);
+
+        let mut handles = core.all_handles();
+        handles.sort_unstable();
+        assert_eq!(handles, vec![3, 7, 0xFFFF_FFF0]);
+        assert_eq!(handles.len(), core.get_node_count());
+    }
+
+    #[test]
+    fn test_count_by_flag() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 0.0, 0.0, 10.0, 10.0, 0.0, FLAG_HIDDEN);
+        core.upsert_node(2, 0.0, 0.0, 10.0, 10.0, 0.0, FLAG_HIDDEN | FLAG_LOCKED);
+        core.upsert_node(3, 0.0, 0.0, 10.0, 10.0, 0.0, 0);
+
+        assert_eq!(core.count_by_flag(FLAG_HIDDEN), 2);
+        assert_eq!(core.count_by_flag(FLAG_LOCKED), 1);
+        assert_eq!(core.count_by_flag(0), 0);
+        assert_eq!(core.count_total(), 3);
+
+        core.set_flags_many(vec![1], 0, FLAG_HIDDEN).unwrap();
+        core.remove_node(2);
+        assert_eq!(core.count_by_flag(FLAG_HIDDEN), 0);
+        assert_eq!(core.count_total(), core.get_node_count());
+    }
+
+    #[test]
+    fn test_snap_to_diagonal() {
+        let mut core = EditorCore::new(10);
+        core.set_snap_rounding(Some(3));
+
+        assert_eq!(core.snap_to_diagonal(0.0, 0.0, 10.0, 12.0, None), vec![11.0, 11.0]);
+        assert_eq!(core.snap_to_diagonal(0.0, 0.0, 3.0, -40.0, None), vec![0.0, -40.0]);
+        // 30 degree steps: 100 units at 25 degrees lands on the 30 degree line
+        let (sin, cos) = 25f32.to_radians().sin_cos();
+        let snapped = core.snap_to_diagonal(0.0, 0.0, 100.0 * cos, 100.0 * sin, Some(30.0));
+        assert!((snapped[1] / snapped[0] - 30f32.to_radians().tan()).abs() < 1e-3);
+
+        assert_eq!(core.snap_to_diagonal(7.0, 7.0, 7.0, 7.0, None), vec![7.0, 7.0]);
+        assert_eq!(core.snap_to_diagonal(0.0, 0.0, 3.0, 4.0, Some(0.0)), vec![3.0, 4.0]);
+    }
+
+    #[test]
+    fn test_hit_test_edge() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 0.0, 0.0, 100.0, 50.0, 0.0, 0);
+        core.upsert_node(2, 200.0, 0.0, 204.0, 50.0, 0.0, 0);
+
+        let probes = [
+            ((2.0, 3.0), 1),    // top-left
+            ((50.0, 1.0), 2),   // top
+            ((98.0, 0.0), 3),   // top-right
+            ((100.0, 25.0), 4), // right
+            ((99.0, 49.0), 5),  // bottom-right
+            ((50.0, 46.0), 6),  // bottom
+            ((4.0, 50.0), 7),   // bottom-left
+            ((0.0, 25.0), 8),   // left
+            ((50.0, 25.0), EDGE_INTERIOR),
+            ((6.0, 25.0), EDGE_INTERIOR), // just past the tolerance
+        ];
+        for ((x, y), code) in probes {
+            assert_eq!(core.hit_test_edge(x, y, 5.0), vec![1, code], "at ({x}, {y})");
+        }
+
+        // A node thinner than twice the tolerance picks the nearer side
+        assert_eq!(core.hit_test_edge(201.0, 25.0, 5.0), vec![2, 8]);
+        assert_eq!(core.hit_test_edge(203.0, 25.0, 5.0), vec![2, 4]);
+
+        assert!(core.hit_test_edge(150.0, 25.0, 5.0).is_empty());
+    }
+
+    #[test]
+    fn test_pinned_nodes_always_culled_in() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 10.0, 10.0, 20.0, 20.0, 0.0, 0);
+        core.upsert_node(2, 0.0, 0.0, 50.0, 50.0, -1.0, FLAG_PINNED);
+        core.upsert_node(3, 5000.0, 5000.0, 5010.0, 5010.0, 0.0, FLAG_PINNED | FLAG_HIDDEN);
+
+        // In view: the pinned node is reported once, after the grid results,
+        // but it is not in any grid cell, so area queries don't see it
+        assert_eq!(core.cull_visible(), vec![1, 2]);
+        assert_eq!(core.query_rect(0.0, 0.0, 50.0, 50.0), vec![1]);
+        assert_eq!(core.hit_test_point(15.0, 15.0), vec![1, 2]);
+
+        // Far from its bounds it is still visible; the hidden one never is
+        core.set_camera(1.0, 100_000.0, 100_000.0, 800.0, 600.0, 1.0, None, None, None, None);
+        assert_eq!(core.cull_visible(), vec![2]);
+        assert_eq!(core.cull_visible_buffered(), 1);
+        assert_eq!(core.cull_visible_with_stats().hidden_count(), 0);
+
+        core.set_flags_many(vec![2], 0, FLAG_PINNED).unwrap();
+        assert!(core.cull_visible().is_empty());
+        assert_eq!(core.query_rect(0.0, 0.0, 50.0, 50.0).len(), 2);
+        core.set_flags_many(vec![2], FLAG_PINNED, 0).unwrap();
+        core.remove_node(2);
+        assert!(core.cull_visible().is_empty());
+    }
+
+    #[test]
+    fn test_audit_spacing() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 0.0, 0.0, 10.0, 10.0, 0.0, 0);
+        core.upsert_node(2, 24.0, 0.0, 34.0, 10.0, 0.0, 0);
+
+        let audit = core.audit_spacing(vec![1, 2, 99]);
+        assert_eq!(audit.len(), 4);
+        assert_eq!((audit[0].to_bits(), audit[1].to_bits()), (1, 2));
+        assert_eq!((audit[2], audit[3]), (0.0, 14.0));
+    }
+
+    #[test]
+    fn test_guide_session() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 0.0, 0.0, 50.0, 50.0, 0.0, 0); // dragged
+        core.upsert_node(2, 200.0, 100.0, 300.0, 150.0, 0.0, 0);
+        assert!(core.update_guide_session(0.0, 0.0, 50.0, 50.0).is_empty());
+
+        core.begin_guide_session(1, vec![1, 2], 1.0, None);
+
+        // Dragged so its top meets node 2's top; the dragged node is not a reference
+        let guides = core.update_guide_session(0.5, 100.5, 50.5, 130.5);
+        assert_eq!(guides.len(), 1);
+        assert_eq!((guides[0].guide_type(), guides[0].position()), (1, 100.5));
+        assert_eq!(guides[0].node_count(), 1);
+
+        // References stay cached even if the scene changes mid-drag
+        core.upsert_node(2, 900.0, 900.0, 950.0, 950.0, 0.0, 0);
+        assert_eq!(core.update_guide_session(0.5, 100.5, 50.5, 130.5).len(), 1);
+
+        core.end_guide_session();
+        assert!(core.update_guide_session(0.5, 100.5, 50.5, 130.5).is_empty());
+    }
+
+    #[test]
+    fn test_smart_guides_exclude_moving_group() {
+        let mut core = EditorCore::new(10);
+        // Moving group: 1 and 2, left-aligned with each other
+        core.upsert_node(1, 0.0, 0.0, 10.0, 10.0, 0.0, 0);
+        core.upsert_node(2, 0.0, 100.0, 20.0, 120.0, 0.0, 0);
+        // Reference node far to the right, sharing nothing with the group
+        core.upsert_node(3, 500.0, 500.0, 530.0, 530.0, 0.0, 0);
+
+        let guides = core.calculate_alignment_guides(1, vec![2, 3], 1.0, None, None);
+        assert!(!guides.is_empty());
+
+        let guides = core.calculate_alignment_guides(1, vec![2, 3], 1.0, Some(vec![1, 2]), None);
+        assert!(guides.is_empty());
+    }
+
+    #[test]
+    fn test_nearest_in_direction() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 100.0, 100.0, 150.0, 150.0, 0.0, 0);
+        // Right: 2 is closer but barely overlaps vertically, 3 fully overlaps
+        core.upsert_node(2, 160.0, 145.0, 200.0, 185.0, 0.0, 0);
+        core.upsert_node(3, 300.0, 100.0, 350.0, 150.0, 0.0, 0);
+        // Left: hidden node is skipped
+        core.upsert_node(4, 0.0, 100.0, 50.0, 150.0, 0.0, FLAG_HIDDEN);
+        // Up
+        core.upsert_node(5, 100.0, 0.0, 150.0, 50.0, 0.0, 0);
+
+        assert_eq!(core.nearest_in_direction(1, DIRECTION_RIGHT), Some(3));
+        assert_eq!(core.nearest_in_direction(1, DIRECTION_LEFT), None);
+        assert_eq!(core.nearest_in_direction(1, DIRECTION_UP), Some(5));
+        assert_eq!(core.nearest_in_direction(1, DIRECTION_DOWN), Some(2));
+        assert_eq!(core.nearest_in_direction(99, DIRECTION_UP), None);
+    }
+
+    #[test]
+    fn test_screen_space_queries() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 100.0, 100.0, 200.0, 200.0, 0.0, 0);
+        core.set_camera(2.0, 150.0, 150.0, 800.0, 600.0, 1.0, None, None, None, None);
+
+        // Viewport center maps to the pan point
+        assert_eq!(core.hit_test_screen(400.0, 300.0), vec![1]);
+        assert!(core.hit_test_screen(0.0, 0.0).is_empty());
+
+        // Reversed corners select the same region
+        assert_eq!(core.query_rect_screen(450.0, 350.0, 350.0, 250.0), vec![1]);
+        assert!(core.query_rect_screen(0.0, 0.0, 50.0, 50.0).is_empty());
+    }
+
+    #[test]
+    fn test_alignment_guides_px_scales_with_zoom() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 0.0, 0.0, 10.0, 10.0, 0.0, 0);
+        core.upsert_node(2, 3.0, 50.0, 13.0, 60.0, 0.0, 0);
+
+        // 3 world units apart: 4px is 4 world units at zoom 1
+        core.set_camera(1.0, 0.0, 0.0, 800.0, 600.0, 1.0, None, None, None, None);
+        assert!(!core.calculate_alignment_guides_px(1, vec![2], 4.0, None, None).is_empty());
+
+        // ...but only 1 world unit at zoom 4
+        core.set_camera(4.0, 0.0, 0.0, 800.0, 600.0, 1.0, None, None, None, None);
+        assert!(core.calculate_alignment_guides_px(1, vec![2], 4.0, None, None).is_empty());
+    }
+
+    #[test]
+    fn test_alignment_guides_to_rect() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 0.5, 400.0, 20.5, 420.0, 0.0, 0); // left edge near the rect's
+        core.upsert_node(2, 90.0, 30.0, 110.0, 70.5, 0.0, 0); // centered on the rect
+
+        let summary = |guides: Vec<AlignmentGuide>| -> Vec<(u8, u8, f32)> {
+            guides.iter().map(|g| (g.guide_type(), g.alignment_type(), g.position())).collect()
+        };
+
+        // Guides sit on the moving node's aligned line (guide type, alignment type, position)
+        let edge = core.calculate_alignment_guides_to_rect(1, 0.0, 0.0, 200.0, 100.0, 1.0);
+        assert_eq!(summary(edge), vec![(0, 0, 0.5)]);
+        let center = core.calculate_alignment_guides_to_rect(2, 0.0, 0.0, 200.0, 100.0, 1.0);
+        assert_eq!(summary(center), vec![(0, 4, 100.0), (1, 5, 50.25)]);
+
+        assert!(core.calculate_alignment_guides_to_rect(99, 0.0, 0.0, 200.0, 100.0, 1.0).is_empty());
+    }
+
+    #[test]
+    fn test_spacing_guides_px_scales_with_zoom() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 0.0, 0.0, 10.0, 10.0, 0.0, 0);
+        core.upsert_node(2, 20.0, 0.0, 30.0, 10.0, 0.0, 0);
+        core.upsert_node(3, 40.3, 0.0, 50.3, 10.0, 0.0, 0);
+
+        // Gaps of 10 and 10.3: within 0.5px at zoom 1...
+        core.set_camera(1.0, 0.0, 0.0, 800.0, 600.0, 1.0, None, None, None, None);
+        let guides = core.calculate_spacing_guides_px(3, vec![1, 2], None, None, None, None, None, None);
+        assert!(!guides.is_empty());
+
+        // ...but 0.5px is only 0.125 world units at zoom 4
+        core.set_camera(4.0, 0.0, 0.0, 800.0, 600.0, 1.0, None, None, None, None);
+        let guides = core.calculate_spacing_guides_px(3, vec![1, 2], None, None, None, None, None, None);
+        assert!(guides.is_empty());
+    }
+
+    #[test]
+    fn test_alignment_guides_to_viewport_center() {
+        let mut core = EditorCore::new(10);
+        core.upsert_node(1, 90.0, 40.5, 110.0, 60.0, 0.0, 0);
+        // Visible world bounds (-300, -250) to (500, 350): center (100, 50)
+        core.set_camera(1.0, 100.0, 50.0, 800.0, 600.0, 1.0, None, None, None, None);
+
+        assert!(core.calculate_alignment_guides(1, vec![], 1.0, None, None).is_empty());
+
+        let guides = core.calculate_alignment_guides(1, vec![], 1.0, None, Some(true));
+        assert_eq!(guides.len(), 2);
+        assert!(guides.iter().all(|g| g.is_viewport()));
+        assert_eq!((guides[0].guide_type(), guides[0].position()), (0, 100.0));
+        assert_eq!((guides[1].guide_type(), guides[1].position()), (1, 50.25));
     }
 }
@@ -18,6 +18,8 @@ pub struct AlignmentGuide {
     position: f32,
     alignment_type: u8, // 0-5 mapping to edge-left, edge-right, etc.
     node_count: usize,
+    span: f32, // perpendicular extent covered by the moving and aligned nodes
+    viewport: bool, // aligned to the viewport's center or edges rather than nodes
 }
 
 #[wasm_bindgen]
@@ -41,6 +43,16 @@ impl AlignmentGuide {
     pub fn node_count(&self) -> usize {
         self.node_count
     }
+
+    #[wasm_bindgen(getter)]
+    pub fn span(&self) -> f32 {
+        self.span
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn is_viewport(&self) -> bool {
+        self.viewport
+    }
 }
 
 /// Spacing guide (shows equal spacing between objects)
@@ -155,6 +167,73 @@ impl DistanceMeasurement {
     pub fn distance(&self) -> f32 {
         self.distance
     }
+
+    /// Suggested label anchor: midpoint of the measurement segment
+    #[wasm_bindgen(getter)]
+    pub fn label_x(&self) -> f32 {
+        (self.from_x + self.to_x) / 2.0
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn label_y(&self) -> f32 {
+        (self.from_y + self.to_y) / 2.0
+    }
+}
+
+/// Cross alignment (a vertical and horizontal guide meeting at a reference node's center)
+#[wasm_bindgen]
+#[derive(Clone, Copy)]
+pub struct CrossAlignment {
+    x: f32,
+    y: f32,
+    node_count: usize,
+}
+
+#[wasm_bindgen]
+impl CrossAlignment {
+    #[wasm_bindgen(getter)]
+    pub fn x(&self) -> f32 {
+        self.x
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn y(&self) -> f32 {
+        self.y
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn node_count(&self) -> usize {
+        self.node_count
+    }
+}
+
+/// Combined smart guides result (alignment, spacing, distance and cross alignment)
+#[wasm_bindgen]
+pub struct SmartGuides {
+    alignment: Vec<AlignmentGuide>,
+    spacing: Vec<SpacingGuide>,
+    distances: Vec<DistanceMeasurement>,
+    cross: Option<CrossAlignment>,
+}
+
+#[wasm_bindgen]
+impl SmartGuides {
+    pub fn alignment_guides(&self) -> Vec<AlignmentGuide> {
+        self.alignment.clone()
+    }
+
+    pub fn spacing_guides(&self) -> Vec<SpacingGuide> {
+        self.spacing.clone()
+    }
+
+    pub fn distance_measurements(&self) -> Vec<DistanceMeasurement> {
+        self.distances.clone()
+    }
+
+    /// Center-in-center pairing of the CENTER_X and CENTER_Y guides, if any
+    pub fn cross_alignment(&self) -> Option<CrossAlignment> {
+        self.cross
+    }
 }
 
 // ============================================================================
@@ -220,6 +299,14 @@ impl NodeBounds {
     }
 }
 
+/// Accumulated alignment at one guide position
+struct AlignmentMatch {
+    alignment_type: u8,
+    node_count: usize,
+    span_min: f32,
+    span_max: f32,
+}
+
 // ============================================================================
 // Alignment Type Constants
 // ============================================================================
@@ -231,6 +318,9 @@ const EDGE_BOTTOM: u8 = 3;
 const CENTER_X: u8 = 4;
 const CENTER_Y: u8 = 5;
 
+const SPACING_HORIZONTAL: u8 = 0;
+const SPACING_VERTICAL: u8 = 1;
+
 // ============================================================================
 // Smart Guide Calculations
 // ============================================================================
@@ -242,8 +332,6 @@ pub fn calculate_alignment_guides(
     all_bounds: &[(f32, f32, f32, f32)],  // [(min_x, min_y, max_x, max_y), ...]
     threshold: f32,
 ) -> Vec<AlignmentGuide> {
-    let mut guides = Vec::new();
-    
     let moving = NodeBounds::from_tuple(moving_bounds);
 
     let left = moving.left();
@@ -254,8 +342,12 @@ pub fn calculate_alignment_guides(
     let center_y = moving.center_y();
 
     // Use u32 as key (bit representation of f32) to handle HashMap deduplication
-    let mut vertical_alignments: HashMap<u32, (u8, usize)> = HashMap::new();
-    let mut horizontal_alignments: HashMap<u32, (u8, usize)> = HashMap::new();
+    let mut vertical_alignments: HashMap<u32, AlignmentMatch> = HashMap::new();
+    let mut horizontal_alignments: HashMap<u32, AlignmentMatch> = HashMap::new();
+
+    // Perpendicular extents used to compute guide spans
+    let moving_y_extent = (top, bottom);
+    let moving_x_extent = (left, right);
 
     for bounds in all_bounds {
         let node = NodeBounds::from_tuple(*bounds);
@@ -267,72 +359,280 @@ pub fn calculate_alignment_guides(
         let n_bottom = node.bottom();
         let n_center_y = node.center_y();
 
-        // Check vertical alignments (x-axis)
-        check_alignment(&mut vertical_alignments, left, n_left, threshold, EDGE_LEFT);
-        check_alignment(&mut vertical_alignments, right, n_right, threshold, EDGE_RIGHT);
-        check_alignment(&mut vertical_alignments, center_x, n_center_x, threshold, CENTER_X);
-        check_alignment(&mut vertical_alignments, left, n_right, threshold, EDGE_LEFT);
-        check_alignment(&mut vertical_alignments, right, n_left, threshold, EDGE_RIGHT);
-
-        // Check horizontal alignments (y-axis)
-        check_alignment(&mut horizontal_alignments, top, n_top, threshold, EDGE_TOP);
-        check_alignment(&mut horizontal_alignments, bottom, n_bottom, threshold, EDGE_BOTTOM);
-        check_alignment(&mut horizontal_alignments, center_y, n_center_y, threshold, CENTER_Y);
-        check_alignment(&mut horizontal_alignments, top, n_bottom, threshold, EDGE_TOP);
-        check_alignment(&mut horizontal_alignments, bottom, n_top, threshold, EDGE_BOTTOM);
+        // Check vertical alignments (x-axis), spanning along y
+        let vertical = &mut vertical_alignments;
+        let extents = (moving_y_extent, (n_top, n_bottom));
+        check_alignment(vertical, left, n_left, threshold, EDGE_LEFT, extents);
+        check_alignment(vertical, right, n_right, threshold, EDGE_RIGHT, extents);
+        check_alignment(vertical, center_x, n_center_x, threshold, CENTER_X, extents);
+        check_alignment(vertical, left, n_right, threshold, EDGE_LEFT, extents);
+        check_alignment(vertical, right, n_left, threshold, EDGE_RIGHT, extents);
+
+        // Check horizontal alignments (y-axis), spanning along x
+        let horizontal = &mut horizontal_alignments;
+        let extents = (moving_x_extent, (n_left, n_right));
+        check_alignment(horizontal, top, n_top, threshold, EDGE_TOP, extents);
+        check_alignment(horizontal, bottom, n_bottom, threshold, EDGE_BOTTOM, extents);
+        check_alignment(horizontal, center_y, n_center_y, threshold, CENTER_Y, extents);
+        check_alignment(horizontal, top, n_bottom, threshold, EDGE_TOP, extents);
+        check_alignment(horizontal, bottom, n_top, threshold, EDGE_BOTTOM, extents);
     }
 
+    alignment_guides_from(vertical_alignments, horizontal_alignments)
+}
+
+/// Convert accumulated alignments to guides, strongest first
+fn alignment_guides_from(
+    vertical_alignments: HashMap<u32, AlignmentMatch>,
+    horizontal_alignments: HashMap<u32, AlignmentMatch>,
+) -> Vec<AlignmentGuide> {
+    let mut guides = Vec::with_capacity(vertical_alignments.len() + horizontal_alignments.len());
+
     // Convert vertical alignments to guides
-    for (pos_key, (align_type, count)) in vertical_alignments {
+    for (pos_key, m) in vertical_alignments {
         guides.push(AlignmentGuide {
             guide_type: 0, // vertical
             position: f32::from_bits(pos_key),
-            alignment_type: align_type,
-            node_count: count,
+            alignment_type: m.alignment_type,
+            node_count: m.node_count,
+            span: m.span_max - m.span_min,
+            viewport: false,
         });
     }
 
     // Convert horizontal alignments to guides
-    for (pos_key, (align_type, count)) in horizontal_alignments {
+    for (pos_key, m) in horizontal_alignments {
         guides.push(AlignmentGuide {
             guide_type: 1, // horizontal
             position: f32::from_bits(pos_key),
-            alignment_type: align_type,
-            node_count: count,
+            alignment_type: m.alignment_type,
+            node_count: m.node_count,
+            span: m.span_max - m.span_min,
+            viewport: false,
         });
     }
 
+    // Strongest guides first, then by orientation and position for determinism
+    guides.sort_by(|a, b| {
+        b.node_count
+            .cmp(&a.node_count)
+            .then(a.guide_type.cmp(&b.guide_type))
+            .then(a.position.total_cmp(&b.position))
+    });
+
+    guides
+}
+
+/// A reference edge or center line with its perpendicular extent
+#[derive(Clone, Copy)]
+struct GuideLine {
+    position: f32,
+    extent: (f32, f32),
+}
+
+/// Alignment guides for one drag, with the reference lines cached up front
+///
+/// Reference edges and centers are collected once and sorted by position, so
+/// each frame only binary-searches the lines within threshold of the moving
+/// node's edges and centers. Produces the same guides as
+/// `calculate_alignment_guides` over the same references.
+pub(crate) struct AlignmentSession {
+    threshold: f32,
+    vertical_edges: Vec<GuideLine>, // left and right edges (x), extent along y
+    vertical_centers: Vec<GuideLine>,
+    horizontal_edges: Vec<GuideLine>, // top and bottom edges (y), extent along x
+    horizontal_centers: Vec<GuideLine>,
+}
+
+impl AlignmentSession {
+    pub(crate) fn new(all_bounds: &[(f32, f32, f32, f32)], threshold: f32) -> Self {
+        let mut session = Self {
+            threshold,
+            vertical_edges: Vec::with_capacity(all_bounds.len() * 2),
+            vertical_centers: Vec::with_capacity(all_bounds.len()),
+            horizontal_edges: Vec::with_capacity(all_bounds.len() * 2),
+            horizontal_centers: Vec::with_capacity(all_bounds.len()),
+        };
+
+        for bounds in all_bounds {
+            let node = NodeBounds::from_tuple(*bounds);
+            let y_extent = (node.top(), node.bottom());
+            let x_extent = (node.left(), node.right());
+
+            for position in [node.left(), node.right()] {
+                session.vertical_edges.push(GuideLine { position, extent: y_extent });
+            }
+            session.vertical_centers.push(GuideLine { position: node.center_x(), extent: y_extent });
+            for position in [node.top(), node.bottom()] {
+                session.horizontal_edges.push(GuideLine { position, extent: x_extent });
+            }
+            session.horizontal_centers.push(GuideLine { position: node.center_y(), extent: x_extent });
+        }
+
+        for lines in [
+            &mut session.vertical_edges,
+            &mut session.vertical_centers,
+            &mut session.horizontal_edges,
+            &mut session.horizontal_centers,
+        ] {
+            lines.sort_unstable_by(|a, b| a.position.total_cmp(&b.position));
+        }
+        session
+    }
+
+    /// Alignment guides for the moving node at `moving_bounds`
+    pub(crate) fn guides(&self, moving_bounds: (f32, f32, f32, f32)) -> Vec<AlignmentGuide> {
+        let moving = NodeBounds::from_tuple(moving_bounds);
+        let mut vertical_alignments: HashMap<u32, AlignmentMatch> = HashMap::new();
+        let mut horizontal_alignments: HashMap<u32, AlignmentMatch> = HashMap::new();
+
+        let y_extent = (moving.top(), moving.bottom());
+        let vertical = &mut vertical_alignments;
+        self.match_lines(vertical, &self.vertical_edges, moving.left(), EDGE_LEFT, y_extent);
+        self.match_lines(vertical, &self.vertical_edges, moving.right(), EDGE_RIGHT, y_extent);
+        self.match_lines(vertical, &self.vertical_centers, moving.center_x(), CENTER_X, y_extent);
+
+        let x_extent = (moving.left(), moving.right());
+        let horizontal = &mut horizontal_alignments;
+        self.match_lines(horizontal, &self.horizontal_edges, moving.top(), EDGE_TOP, x_extent);
+        self.match_lines(horizontal, &self.horizontal_edges, moving.bottom(), EDGE_BOTTOM, x_extent);
+        self.match_lines(horizontal, &self.horizontal_centers, moving.center_y(), CENTER_Y, x_extent);
+
+        alignment_guides_from(vertical_alignments, horizontal_alignments)
+    }
+
+    /// Record an alignment at `position` for every line strictly within threshold
+    fn match_lines(
+        &self,
+        map: &mut HashMap<u32, AlignmentMatch>,
+        lines: &[GuideLine],
+        position: f32,
+        align_type: u8,
+        moving_extent: (f32, f32),
+    ) {
+        let start = lines.partition_point(|line| line.position <= position - self.threshold);
+        for line in lines[start..].iter().take_while(|line| line.position < position + self.threshold) {
+            check_alignment(map, position, line.position, self.threshold, align_type, (moving_extent, line.extent));
+        }
+    }
+}
+
+/// Calculate alignment guides against the viewport's edges and center lines
+/// Guides sit at the moving node's coordinate (like node guides), have a
+/// `node_count` of 0 and span the viewport's full perpendicular extent.
+pub fn calculate_viewport_guides(
+    moving_bounds: (f32, f32, f32, f32), // (min_x, min_y, max_x, max_y)
+    viewport: (f32, f32, f32, f32),      // visible world bounds
+    threshold: f32,
+) -> Vec<AlignmentGuide> {
+    let moving = NodeBounds::from_tuple(moving_bounds);
+    let view = NodeBounds::from_tuple(viewport);
+
+    let vertical = [
+        (moving.left(), view.left(), EDGE_LEFT),
+        (moving.center_x(), view.center_x(), CENTER_X),
+        (moving.right(), view.right(), EDGE_RIGHT),
+    ];
+    let horizontal = [
+        (moving.top(), view.top(), EDGE_TOP),
+        (moving.center_y(), view.center_y(), CENTER_Y),
+        (moving.bottom(), view.bottom(), EDGE_BOTTOM),
+    ];
+
+    let guide = |guide_type: u8, span: f32| {
+        move |&(position, line, alignment_type): &(f32, f32, u8)| {
+            ((position - line).abs() < threshold).then_some(AlignmentGuide {
+                guide_type,
+                position,
+                alignment_type,
+                node_count: 0,
+                span,
+                viewport: true,
+            })
+        }
+    };
+
+    let mut guides: Vec<AlignmentGuide> = vertical.iter().filter_map(guide(0, view.height())).collect();
+    guides.extend(horizontal.iter().filter_map(guide(1, view.width())));
     guides
 }
 
+/// Check whether the same edge or center of two boxes aligns within threshold
+/// `alignment_type` uses the guide constants (0 = left edges, 1 = right, 2 = top,
+/// 3 = bottom, 4 = center x, 5 = center y); unknown types never align.
+/// The threshold is inclusive, so 0 checks for exact alignment.
+pub fn is_aligned(
+    a: (f32, f32, f32, f32), // (min_x, min_y, max_x, max_y)
+    b: (f32, f32, f32, f32),
+    alignment_type: u8,
+    threshold: f32,
+) -> bool {
+    let a = NodeBounds::from_tuple(a);
+    let b = NodeBounds::from_tuple(b);
+
+    let coordinate: fn(&NodeBounds) -> f32 = match alignment_type {
+        EDGE_LEFT => NodeBounds::left,
+        EDGE_RIGHT => NodeBounds::right,
+        EDGE_TOP => NodeBounds::top,
+        EDGE_BOTTOM => NodeBounds::bottom,
+        CENTER_X => NodeBounds::center_x,
+        CENTER_Y => NodeBounds::center_y,
+        _ => return false,
+    };
+    (coordinate(&a) - coordinate(&b)).abs() <= threshold
+}
+
+/// Record an alignment at `pos1` if `pos2` is within threshold
+/// `extents` holds the (min, max) perpendicular extents of the moving and other node
 fn check_alignment(
-    map: &mut HashMap<u32, (u8, usize)>,
+    map: &mut HashMap<u32, AlignmentMatch>,
     pos1: f32,
     pos2: f32,
     threshold: f32,
     align_type: u8,
+    extents: ((f32, f32), (f32, f32)),
 ) {
     if (pos1 - pos2).abs() < threshold {
+        let (moving_extent, node_extent) = extents;
         let key = pos1.to_bits();
         map.entry(key)
-            .and_modify(|(_, count)| *count += 1)
-            .or_insert((align_type, 1));
+            .and_modify(|m| {
+                m.node_count += 1;
+                m.span_min = m.span_min.min(node_extent.0);
+                m.span_max = m.span_max.max(node_extent.1);
+            })
+            .or_insert(AlignmentMatch {
+                alignment_type: align_type,
+                node_count: 1,
+                span_min: moving_extent.0.min(node_extent.0),
+                span_max: moving_extent.1.max(node_extent.1),
+            });
     }
 }
 
+/// Default tolerance (world units) under which two gaps count as equal
+pub const DEFAULT_SPACING_TOLERANCE: f32 = 0.5;
+
 /// Calculate spacing guides
-/// Shows when spacing between objects is equal
+/// Shows when spacing between objects is equal: either the moving node
+/// repeats a gap that already exists between two other nodes, or it sits
+/// between two nodes with equal gaps on both sides (A - gap - moving - gap - B).
+/// Inside a parent, equal margins to both parent edges on an axis are flagged
+/// too, with each parent edge given as a zero-thickness rect along that edge.
+/// Gaps within `tolerance` of each other are treated as equal.
 pub fn calculate_spacing_guides(
     moving_bounds: (f32, f32, f32, f32), // (min_x, min_y, max_x, max_y)
     all_bounds: &[(f32, f32, f32, f32)],  // [(min_x, min_y, max_x, max_y), ...]
+    parent_bounds: Option<(f32, f32, f32, f32)>, // (x, y, width, height)
+    tolerance: f32,
 ) -> Vec<SpacingGuide> {
     let mut guides = Vec::new();
     
     let moving = NodeBounds::from_tuple(moving_bounds);
 
-    // Find potential spacing matches (use u32 for bit representation)
-    let mut horizontal_spacings: HashMap<u32, Vec<(NodeBounds, NodeBounds)>> = HashMap::new();
-    let mut vertical_spacings: HashMap<u32, Vec<(NodeBounds, NodeBounds)>> = HashMap::new();
+    // Existing gaps between reference nodes, bucketed by tolerance
+    let mut horizontal_spacings: HashMap<i64, Vec<f32>> = HashMap::new();
+    let mut vertical_spacings: HashMap<i64, Vec<f32>> = HashMap::new();
 
     // Calculate spacings between all pairs of nodes (excluding moving node)
     for i in 0..all_bounds.len() {
@@ -343,21 +643,19 @@ pub fn calculate_spacing_guides(
             // Horizontal spacing (left-right)
             if node2.left() > node1.right() {
                 let spacing = node2.left() - node1.right();
-                let spacing_key = spacing.to_bits();
                 horizontal_spacings
-                    .entry(spacing_key)
-                    .or_insert_with(Vec::new)
-                    .push((node1, node2));
+                    .entry(spacing_bucket(spacing, tolerance))
+                    .or_default()
+                    .push(spacing);
             }
 
             // Vertical spacing (top-bottom)
             if node2.top() > node1.bottom() {
                 let spacing = node2.top() - node1.bottom();
-                let spacing_key = spacing.to_bits();
                 vertical_spacings
-                    .entry(spacing_key)
-                    .or_insert_with(Vec::new)
-                    .push((node1, node2));
+                    .entry(spacing_bucket(spacing, tolerance))
+                    .or_default()
+                    .push(spacing);
             }
         }
     }
@@ -369,95 +667,157 @@ pub fn calculate_spacing_guides(
         // Horizontal spacing
         if node.left() > moving.right() {
             let spacing = node.left() - moving.right();
-            let spacing_key = spacing.to_bits();
-            if let Some(matches) = horizontal_spacings.get(&spacing_key) {
-                if !matches.is_empty() {
-                    guides.push(SpacingGuide {
-                        guide_type: 0, // horizontal
-                        from_x: moving.x(),
-                        from_y: moving.y(),
-                        from_width: moving.width(),
-                        from_height: moving.height(),
-                        to_x: node.x(),
-                        to_y: node.y(),
-                        to_width: node.width(),
-                        to_height: node.height(),
-                        spacing,
-                    });
-                }
+            if has_matching_spacing(&horizontal_spacings, spacing, tolerance) {
+                guides.push(spacing_guide(SPACING_HORIZONTAL, &moving, &node, spacing));
             }
         } else if moving.left() > node.right() {
             let spacing = moving.left() - node.right();
-            let spacing_key = spacing.to_bits();
-            if let Some(matches) = horizontal_spacings.get(&spacing_key) {
-                if !matches.is_empty() {
-                    guides.push(SpacingGuide {
-                        guide_type: 0, // horizontal
-                        from_x: node.x(),
-                        from_y: node.y(),
-                        from_width: node.width(),
-                        from_height: node.height(),
-                        to_x: moving.x(),
-                        to_y: moving.y(),
-                        to_width: moving.width(),
-                        to_height: moving.height(),
-                        spacing,
-                    });
-                }
+            if has_matching_spacing(&horizontal_spacings, spacing, tolerance) {
+                guides.push(spacing_guide(SPACING_HORIZONTAL, &node, &moving, spacing));
             }
         }
 
         // Vertical spacing
         if node.top() > moving.bottom() {
             let spacing = node.top() - moving.bottom();
-            let spacing_key = spacing.to_bits();
-            if let Some(matches) = vertical_spacings.get(&spacing_key) {
-                if !matches.is_empty() {
-                    guides.push(SpacingGuide {
-                        guide_type: 1, // vertical
-                        from_x: moving.x(),
-                        from_y: moving.y(),
-                        from_width: moving.width(),
-                        from_height: moving.height(),
-                        to_x: node.x(),
-                        to_y: node.y(),
-                        to_width: node.width(),
-                        to_height: node.height(),
-                        spacing,
-                    });
-                }
+            if has_matching_spacing(&vertical_spacings, spacing, tolerance) {
+                guides.push(spacing_guide(SPACING_VERTICAL, &moving, &node, spacing));
             }
         } else if moving.top() > node.bottom() {
             let spacing = moving.top() - node.bottom();
-            let spacing_key = spacing.to_bits();
-            if let Some(matches) = vertical_spacings.get(&spacing_key) {
-                if !matches.is_empty() {
-                    guides.push(SpacingGuide {
-                        guide_type: 1, // vertical
-                        from_x: node.x(),
-                        from_y: node.y(),
-                        from_width: node.width(),
-                        from_height: node.height(),
-                        to_x: moving.x(),
-                        to_y: moving.y(),
-                        to_width: moving.width(),
-                        to_height: moving.height(),
-                        spacing,
-                    });
-                }
+            if has_matching_spacing(&vertical_spacings, spacing, tolerance) {
+                guides.push(spacing_guide(SPACING_VERTICAL, &node, &moving, spacing));
             }
         }
     }
 
+    // Check if moving node sits between its nearest neighbors with equal gaps.
+    // Neighbors must overlap the moving node perpendicularly (same row or
+    // column); on ties the first listed is used, so each side yields one guide.
+    let nodes: Vec<NodeBounds> = all_bounds.iter().map(|&b| NodeBounds::from_tuple(b)).collect();
+    let same_row = |n: &NodeBounds| n.top() < moving.bottom() && moving.top() < n.bottom();
+    let same_column = |n: &NodeBounds| n.left() < moving.right() && moving.left() < n.right();
+
+    // Horizontal: A | moving | B
+    let before = nearest_neighbor(&nodes, |n| same_row(n).then(|| moving.left() - n.right()));
+    let after = nearest_neighbor(&nodes, |n| same_row(n).then(|| n.left() - moving.right()));
+    if let (Some((a, spacing)), Some((b, after_gap))) = (before, after) {
+        if (spacing - after_gap).abs() <= tolerance {
+            push_unique(&mut guides, spacing_guide(SPACING_HORIZONTAL, a, &moving, spacing));
+            push_unique(&mut guides, spacing_guide(SPACING_HORIZONTAL, &moving, b, spacing));
+        }
+    }
+
+    // Vertical: A above moving above B
+    let before = nearest_neighbor(&nodes, |n| same_column(n).then(|| moving.top() - n.bottom()));
+    let after = nearest_neighbor(&nodes, |n| same_column(n).then(|| n.top() - moving.bottom()));
+    if let (Some((a, spacing)), Some((b, after_gap))) = (before, after) {
+        if (spacing - after_gap).abs() <= tolerance {
+            push_unique(&mut guides, spacing_guide(SPACING_VERTICAL, a, &moving, spacing));
+            push_unique(&mut guides, spacing_guide(SPACING_VERTICAL, &moving, b, spacing));
+        }
+    }
+
+    // Check if moving node has equal margins inside its parent
+    if let Some((x, y, width, height)) = parent_bounds {
+        let parent = NodeBounds::from_tuple((x, y, x + width, y + height));
+
+        let left = moving.left() - parent.left();
+        let right = parent.right() - moving.right();
+        if left > 0.0 && right > 0.0 && (left - right).abs() <= tolerance {
+            let left_edge = NodeBounds::from_tuple((parent.left(), parent.top(), parent.left(), parent.bottom()));
+            let right_edge = NodeBounds::from_tuple((parent.right(), parent.top(), parent.right(), parent.bottom()));
+            guides.push(spacing_guide(SPACING_HORIZONTAL, &left_edge, &moving, left));
+            guides.push(spacing_guide(SPACING_HORIZONTAL, &moving, &right_edge, left));
+        }
+
+        let top = moving.top() - parent.top();
+        let bottom = parent.bottom() - moving.bottom();
+        if top > 0.0 && bottom > 0.0 && (top - bottom).abs() <= tolerance {
+            let top_edge = NodeBounds::from_tuple((parent.left(), parent.top(), parent.right(), parent.top()));
+            let bottom_edge = NodeBounds::from_tuple((parent.left(), parent.bottom(), parent.right(), parent.bottom()));
+            guides.push(spacing_guide(SPACING_VERTICAL, &top_edge, &moving, top));
+            guides.push(spacing_guide(SPACING_VERTICAL, &moving, &bottom_edge, top));
+        }
+    }
+
     guides
 }
 
+/// Bucket a gap so that gaps within `tolerance` land in the same or an
+/// adjacent bucket (exact bits when tolerance is zero)
+fn spacing_bucket(spacing: f32, tolerance: f32) -> i64 {
+    if tolerance > 0.0 {
+        (spacing / tolerance).floor() as i64
+    } else {
+        spacing.to_bits() as i64
+    }
+}
+
+/// Check whether any recorded gap is within `tolerance` of `spacing`
+fn has_matching_spacing(spacings: &HashMap<i64, Vec<f32>>, spacing: f32, tolerance: f32) -> bool {
+    let bucket = spacing_bucket(spacing, tolerance);
+    (bucket - 1..=bucket + 1)
+        .filter_map(|b| spacings.get(&b))
+        .flatten()
+        .any(|&other| (other - spacing).abs() <= tolerance)
+}
+
+/// The node with the smallest positive gap (the first on ties); `gap` is None
+/// for nodes that don't qualify, and overlapping nodes have no positive gap
+fn nearest_neighbor(
+    nodes: &[NodeBounds],
+    gap: impl Fn(&NodeBounds) -> Option<f32>,
+) -> Option<(&NodeBounds, f32)> {
+    nodes
+        .iter()
+        .filter_map(|n| gap(n).filter(|&g| g > 0.0).map(|g| (n, g)))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+}
+
+/// Push a guide unless one between the same two rects is already present
+fn push_unique(guides: &mut Vec<SpacingGuide>, guide: SpacingGuide) {
+    let rects = |g: &SpacingGuide| {
+        (g.guide_type, [g.from_x, g.from_y, g.from_width, g.from_height, g.to_x, g.to_y, g.to_width, g.to_height])
+    };
+    if !guides.iter().any(|g| rects(g) == rects(&guide)) {
+        guides.push(guide);
+    }
+}
+
+fn spacing_guide(guide_type: u8, from: &NodeBounds, to: &NodeBounds, spacing: f32) -> SpacingGuide {
+    SpacingGuide {
+        guide_type,
+        from_x: from.x(),
+        from_y: from.y(),
+        from_width: from.width(),
+        from_height: from.height(),
+        to_x: to.x(),
+        to_y: to.y(),
+        to_width: to.width(),
+        to_height: to.height(),
+        spacing,
+    }
+}
+
+/// Choose which of a side's nearest sibling and parent edge to report, given
+/// their distances: the closer one (sibling wins ties), or both when `both` is set
+fn pick_measurements(sibling: Option<f32>, parent: Option<f32>, both: bool) -> (bool, bool) {
+    match (sibling, parent) {
+        (Some(s), Some(p)) if !both => (s <= p, p < s),
+        _ => (sibling.is_some(), parent.is_some()),
+    }
+}
+
 /// Calculate distance measurements
-/// Shows distance from object to nearest siblings or parent bounds
+/// Shows distance from object to nearest siblings or parent bounds.
+/// Each side reports whichever of the nearest sibling and the parent edge is
+/// closer, or both when `always_measure_parent` is set.
 pub fn calculate_distance_measurements(
     moving_bounds: (f32, f32, f32, f32),  // (min_x, min_y, max_x, max_y)
     all_bounds: &[(f32, f32, f32, f32)],   // [(min_x, min_y, max_x, max_y), ...]
     parent_bounds: Option<(f32, f32, f32, f32)>, // Optional parent container
+    always_measure_parent: bool,
 ) -> Vec<DistanceMeasurement> {
     let mut measurements = Vec::new();
     
@@ -514,12 +874,31 @@ pub fn calculate_distance_measurements(
         }
     }
 
-    // Add measurements for nearest objects or parent bounds
+    // Add measurements for nearest objects and/or parent bounds
     let center_y = moving.center_y();
     let center_x = moving.center_x();
 
+    // Parent edge (position, distance) per side, when the edge lies beyond the moving node
+    let parent_left = parent_bounds
+        .map(|(px, _, _, _)| (px, moving.left() - px))
+        .filter(|&(_, d)| d > 0.0);
+    let parent_right = parent_bounds
+        .map(|(px, _, pwidth, _)| (px + pwidth, px + pwidth - moving.right()))
+        .filter(|&(_, d)| d > 0.0);
+    let parent_top = parent_bounds
+        .map(|(_, py, _, _)| (py, moving.top() - py))
+        .filter(|&(_, d)| d > 0.0);
+    let parent_bottom = parent_bounds
+        .map(|(_, py, _, pheight)| (py + pheight, py + pheight - moving.bottom()))
+        .filter(|&(_, d)| d > 0.0);
+
     // Left side
-    if let Some((node, distance)) = nearest_left {
+    let (show_sibling, show_parent) = pick_measurements(
+        nearest_left.map(|(_, d)| d),
+        parent_left.map(|(_, d)| d),
+        always_measure_parent,
+    );
+    if let Some((node, distance)) = nearest_left.filter(|_| show_sibling) {
         measurements.push(DistanceMeasurement {
             from_x: moving.left(),
             from_y: center_y,
@@ -528,22 +907,25 @@ pub fn calculate_distance_measurements(
             direction: 0, // horizontal
             distance,
         });
-    } else if let Some((px, _py, _pwidth, _pheight)) = parent_bounds {
-        let dist_to_parent_left = moving.left() - px;
-        if dist_to_parent_left > 0.0 {
-            measurements.push(DistanceMeasurement {
-                from_x: moving.left(),
-                from_y: center_y,
-                to_x: px,
-                to_y: center_y,
-                direction: 0, // horizontal
-                distance: dist_to_parent_left,
-            });
-        }
+    }
+    if let Some((px, distance)) = parent_left.filter(|_| show_parent) {
+        measurements.push(DistanceMeasurement {
+            from_x: moving.left(),
+            from_y: center_y,
+            to_x: px,
+            to_y: center_y,
+            direction: 0, // horizontal
+            distance,
+        });
     }
 
     // Right side
-    if let Some((node, distance)) = nearest_right {
+    let (show_sibling, show_parent) = pick_measurements(
+        nearest_right.map(|(_, d)| d),
+        parent_right.map(|(_, d)| d),
+        always_measure_parent,
+    );
+    if let Some((node, distance)) = nearest_right.filter(|_| show_sibling) {
         measurements.push(DistanceMeasurement {
             from_x: moving.right(),
             from_y: center_y,
@@ -552,22 +934,25 @@ pub fn calculate_distance_measurements(
             direction: 0, // horizontal
             distance,
         });
-    } else if let Some((px, _py, pwidth, _pheight)) = parent_bounds {
-        let dist_to_parent_right = (px + pwidth) - moving.right();
-        if dist_to_parent_right > 0.0 {
-            measurements.push(DistanceMeasurement {
-                from_x: moving.right(),
-                from_y: center_y,
-                to_x: px + pwidth,
-                to_y: center_y,
-                direction: 0, // horizontal
-                distance: dist_to_parent_right,
-            });
-        }
+    }
+    if let Some((px, distance)) = parent_right.filter(|_| show_parent) {
+        measurements.push(DistanceMeasurement {
+            from_x: moving.right(),
+            from_y: center_y,
+            to_x: px,
+            to_y: center_y,
+            direction: 0, // horizontal
+            distance,
+        });
     }
 
     // Top side
-    if let Some((node, distance)) = nearest_top {
+    let (show_sibling, show_parent) = pick_measurements(
+        nearest_top.map(|(_, d)| d),
+        parent_top.map(|(_, d)| d),
+        always_measure_parent,
+    );
+    if let Some((node, distance)) = nearest_top.filter(|_| show_sibling) {
         measurements.push(DistanceMeasurement {
             from_x: center_x,
             from_y: moving.top(),
@@ -576,22 +961,25 @@ pub fn calculate_distance_measurements(
             direction: 1, // vertical
             distance,
         });
-    } else if let Some((_px, py, _pwidth, _pheight)) = parent_bounds {
-        let dist_to_parent_top = moving.top() - py;
-        if dist_to_parent_top > 0.0 {
-            measurements.push(DistanceMeasurement {
-                from_x: center_x,
-                from_y: moving.top(),
-                to_x: center_x,
-                to_y: py,
-                direction: 1, // vertical
-                distance: dist_to_parent_top,
-            });
-        }
+    }
+    if let Some((py, distance)) = parent_top.filter(|_| show_parent) {
+        measurements.push(DistanceMeasurement {
+            from_x: center_x,
+            from_y: moving.top(),
+            to_x: center_x,
+            to_y: py,
+            direction: 1, // vertical
+            distance,
+        });
     }
 
     // Bottom side
-    if let Some((node, distance)) = nearest_bottom {
+    let (show_sibling, show_parent) = pick_measurements(
+        nearest_bottom.map(|(_, d)| d),
+        parent_bottom.map(|(_, d)| d),
+        always_measure_parent,
+    );
+    if let Some((node, distance)) = nearest_bottom.filter(|_| show_sibling) {
         measurements.push(DistanceMeasurement {
             from_x: center_x,
             from_y: moving.bottom(),
@@ -600,19 +988,444 @@ pub fn calculate_distance_measurements(
             direction: 1, // vertical
             distance,
         });
-    } else if let Some((_px, py, _pwidth, pheight)) = parent_bounds {
-        let dist_to_parent_bottom = (py + pheight) - moving.bottom();
-        if dist_to_parent_bottom > 0.0 {
-            measurements.push(DistanceMeasurement {
-                from_x: center_x,
-                from_y: moving.bottom(),
-                to_x: center_x,
-                to_y: py + pheight,
-                direction: 1, // vertical
-                distance: dist_to_parent_bottom,
-            });
+    }
+    if let Some((py, distance)) = parent_bottom.filter(|_| show_parent) {
+        measurements.push(DistanceMeasurement {
+            from_x: center_x,
+            from_y: moving.bottom(),
+            to_x: center_x,
+            to_y: py,
+            direction: 1, // vertical
+            distance,
+        });
+    }
+
+    measurements
+}
+
+/// A node handle with its `(min_x, min_y, max_x, max_y)` bounds
+pub type HandleBounds = (u32, (f32, f32, f32, f32));
+
+/// Gaps between adjacent nodes across a whole set, for spacing audits
+///
+/// B is A's horizontal neighbor when it is the nearest node entirely to A's
+/// right (B.left >= A.right) whose vertical extent overlaps A's (touching
+/// doesn't count); vertical neighbors are the same below A. Equal gaps go to
+/// the lower handle. Returns `(a, b, axis, gap)` with axis 0 = horizontal,
+/// 1 = vertical, in input order of A, each A's horizontal pair first.
+/// Compares every pair, so it is meant for on-demand audits, not per frame.
+pub fn audit_spacing(nodes: &[HandleBounds]) -> Vec<(u32, u32, u8, f32)> {
+    let mut gaps = Vec::new();
+
+    for &(a_handle, a_bounds) in nodes {
+        let a = NodeBounds::from_tuple(a_bounds);
+        let mut right: Option<(f32, u32)> = None;
+        let mut below: Option<(f32, u32)> = None;
+
+        for &(b_handle, b_bounds) in nodes {
+            if b_handle == a_handle {
+                continue;
+            }
+            let b = NodeBounds::from_tuple(b_bounds);
+            let closer = |best: Option<(f32, u32)>, gap: f32| {
+                best.is_none_or(|(d, h)| gap < d || (gap == d && b_handle < h))
+            };
+
+            if b.left() >= a.right() && a.top() < b.bottom() && b.top() < a.bottom() {
+                let gap = b.left() - a.right();
+                if closer(right, gap) {
+                    right = Some((gap, b_handle));
+                }
+            }
+            if b.top() >= a.bottom() && a.left() < b.right() && b.left() < a.right() {
+                let gap = b.top() - a.bottom();
+                if closer(below, gap) {
+                    below = Some((gap, b_handle));
+                }
+            }
+        }
+
+        if let Some((gap, b_handle)) = right {
+            gaps.push((a_handle, b_handle, SPACING_HORIZONTAL, gap));
+        }
+        if let Some((gap, b_handle)) = below {
+            gaps.push((a_handle, b_handle, SPACING_VERTICAL, gap));
         }
     }
 
+    gaps
+}
+
+/// Calculate distance measurements from a node to a point
+/// Measures from the nearest vertical edge (horizontally) and the nearest
+/// horizontal edge (vertically) to the point, along lines clamped to the node's extent
+pub fn calculate_point_measurements(
+    bounds: (f32, f32, f32, f32), // (min_x, min_y, max_x, max_y)
+    point: (f32, f32),
+) -> Vec<DistanceMeasurement> {
+    let mut measurements = Vec::new();
+
+    let node = NodeBounds::from_tuple(bounds);
+    let (px, py) = point;
+
+    // Horizontal: nearest of left/right edge, at the point's y clamped to the node
+    let edge_x = if (px - node.left()).abs() <= (px - node.right()).abs() {
+        node.left()
+    } else {
+        node.right()
+    };
+    let distance_x = (px - edge_x).abs();
+    if distance_x > 0.0 {
+        let y = py.clamp(node.top(), node.bottom());
+        measurements.push(DistanceMeasurement {
+            from_x: edge_x,
+            from_y: y,
+            to_x: px,
+            to_y: y,
+            direction: 0, // horizontal
+            distance: distance_x,
+        });
+    }
+
+    // Vertical: nearest of top/bottom edge, at the point's x clamped to the node
+    let edge_y = if (py - node.top()).abs() <= (py - node.bottom()).abs() {
+        node.top()
+    } else {
+        node.bottom()
+    };
+    let distance_y = (py - edge_y).abs();
+    if distance_y > 0.0 {
+        let x = px.clamp(node.left(), node.right());
+        measurements.push(DistanceMeasurement {
+            from_x: x,
+            from_y: edge_y,
+            to_x: x,
+            to_y: py,
+            direction: 1, // vertical
+            distance: distance_y,
+        });
+    }
+
     measurements
 }
+
+/// Calculate all smart guides for a moving node in one pass
+/// Alignment uses `threshold`, spacing uses `spacing_tolerance`; see the individual
+/// calculations for details.
+pub fn compute_smart_guides(
+    moving_bounds: (f32, f32, f32, f32), // (min_x, min_y, max_x, max_y)
+    all_bounds: &[(f32, f32, f32, f32)],  // [(min_x, min_y, max_x, max_y), ...]
+    threshold: f32,
+    spacing_tolerance: f32,
+    parent_bounds: Option<(f32, f32, f32, f32)>, // (x, y, width, height)
+    always_measure_parent: bool,
+) -> SmartGuides {
+    SmartGuides {
+        alignment: calculate_alignment_guides(moving_bounds, all_bounds, threshold),
+        spacing: calculate_spacing_guides(moving_bounds, all_bounds, parent_bounds, spacing_tolerance),
+        distances: calculate_distance_measurements(
+            moving_bounds,
+            all_bounds,
+            parent_bounds,
+            always_measure_parent,
+        ),
+        cross: find_cross_alignment(moving_bounds, all_bounds, threshold),
+    }
+}
+
+/// Find reference nodes whose center matches the moving node's center on both axes
+/// Both guides sit at the moving node's center, so all matches share one cross.
+fn find_cross_alignment(
+    moving_bounds: (f32, f32, f32, f32),
+    all_bounds: &[(f32, f32, f32, f32)],
+    threshold: f32,
+) -> Option<CrossAlignment> {
+    let moving = NodeBounds::from_tuple(moving_bounds);
+    let center_x = moving.center_x();
+    let center_y = moving.center_y();
+
+    let node_count = all_bounds
+        .iter()
+        .map(|bounds| NodeBounds::from_tuple(*bounds))
+        .filter(|node| {
+            (center_x - node.center_x()).abs() < threshold
+                && (center_y - node.center_y()).abs() < threshold
+        })
+        .count();
+
+    (node_count > 0).then_some(CrossAlignment {
+        x: center_x,
+        y: center_y,
+        node_count,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alignment_guides_sorted_by_strength() {
+        let moving = (0.0, 0.0, 10.0, 10.0);
+        let others = [
+            // Three nodes sharing the moving node's left edge
+            (0.0, 100.0, 5.0, 105.0),
+            (0.0, 200.0, 7.0, 207.0),
+            (0.0, 300.0, 30.0, 330.0),
+            // One node sharing the moving node's top edge
+            (500.0, 0.0, 520.0, 30.0),
+        ];
+
+        let guides = calculate_alignment_guides(moving, &others, 1.0);
+        assert_eq!(guides.len(), 2);
+        assert_eq!(guides[0].node_count, 3);
+        assert_eq!(guides[0].guide_type, 0);
+        assert_eq!(guides[1].node_count, 1);
+        assert_eq!(guides[1].guide_type, 1);
+
+        // Left-edge guide covers the moving node down to the lowest aligned node
+        assert_eq!(guides[0].span, 330.0);
+        // Top-edge guide covers moving node through the node at x = 500..520
+        assert_eq!(guides[1].span, 520.0);
+    }
+
+    #[test]
+    fn test_alignment_session_matches_full_calculation() {
+        let others: Vec<(f32, f32, f32, f32)> = (0..200)
+            .map(|i| {
+                let x = (i % 20) as f32 * 37.0;
+                let y = (i / 20) as f32 * 53.0;
+                (x, y, x + 10.0 + (i % 7) as f32, y + 12.0 + (i % 5) as f32)
+            })
+            .collect();
+        let session = AlignmentSession::new(&others, 1.5);
+
+        let mut matched = 0;
+        for step in 0..50 {
+            let offset = step as f32 * 3.7;
+            let moving = (offset, offset * 0.5, offset + 24.0, offset * 0.5 + 18.0);
+
+            let expected = calculate_alignment_guides(moving, &others, 1.5);
+            let actual = session.guides(moving);
+            let summary = |guides: &[AlignmentGuide]| -> Vec<(u8, u32, u8, usize, u32)> {
+                guides
+                    .iter()
+                    .map(|g| (g.guide_type, g.position.to_bits(), g.alignment_type, g.node_count, g.span.to_bits()))
+                    .collect()
+            };
+            assert_eq!(summary(&actual), summary(&expected));
+            matched += actual.len();
+        }
+        assert!(matched > 0);
+    }
+
+    #[test]
+    fn test_spacing_guides_equal_gaps_around_moving() {
+        // Moving node centered between A and B with 40-unit gaps
+        let a = (0.0, 0.0, 50.0, 50.0);
+        let b = (200.0, 0.0, 250.0, 50.0);
+        let moving = (90.0, 0.0, 160.0, 50.0);
+
+        let guides = calculate_spacing_guides(moving, &[a, b], None, 0.0);
+        assert_eq!(guides.len(), 2);
+        assert!(guides.iter().all(|g| g.guide_type == SPACING_HORIZONTAL && g.spacing == 40.0));
+        assert_eq!((guides[0].from_x, guides[0].to_x), (0.0, 90.0));
+        assert_eq!((guides[1].from_x, guides[1].to_x), (90.0, 200.0));
+
+        // Off-center produces no guide
+        let moving = (95.0, 0.0, 165.0, 50.0);
+        assert!(calculate_spacing_guides(moving, &[a, b], None, 0.0).is_empty());
+    }
+
+    #[test]
+    fn test_spacing_guides_between_nearest_neighbors_only() {
+        // Two left neighbors share an x: the moving-B guide appears once
+        let a1 = (0.0, 0.0, 50.0, 20.0);
+        let a2 = (0.0, 30.0, 50.0, 50.0);
+        let b = (200.0, 0.0, 250.0, 50.0);
+        let moving = (90.0, 0.0, 160.0, 50.0);
+
+        let guides = calculate_spacing_guides(moving, &[a1, a2, b], None, 0.0);
+        assert_eq!(guides.len(), 2);
+        assert_eq!((guides[0].from_y, guides[1].to_x), (0.0, 200.0));
+
+        // Nodes in another row don't form a triple with the moving node
+        let c = (0.0, 500.0, 50.0, 550.0);
+        let d = (200.0, 500.0, 250.0, 550.0);
+        assert!(calculate_spacing_guides(moving, &[c, d], None, 0.0).is_empty());
+    }
+
+    #[test]
+    fn test_spacing_guides_tolerance() {
+        // Existing gap of 20.0 between A and B; moving sits 20.3 right of B
+        let a = (0.0, 0.0, 50.0, 50.0);
+        let b = (70.0, 0.0, 120.0, 50.0);
+        let moving = (140.3, 0.0, 190.3, 50.0);
+
+        let guides = calculate_spacing_guides(moving, &[a, b], None, 0.5);
+        assert!(guides.iter().any(|g| g.guide_type == SPACING_HORIZONTAL && g.from_x == 70.0));
+
+        assert!(calculate_spacing_guides(moving, &[a, b], None, 0.0).is_empty());
+    }
+
+    #[test]
+    fn test_spacing_guides_equal_parent_margins() {
+        // 100-wide node centered horizontally in a 300x200 frame at (100, 100)
+        let frame = Some((100.0, 100.0, 300.0, 200.0));
+        let moving = (200.0, 120.0, 300.0, 170.0);
+
+        let guides = calculate_spacing_guides(moving, &[], frame, 0.5);
+        assert_eq!(guides.len(), 2);
+        assert!(guides.iter().all(|g| g.guide_type == SPACING_HORIZONTAL && g.spacing == 100.0));
+        assert_eq!((guides[0].from_x, guides[0].from_width, guides[0].to_x), (100.0, 0.0, 200.0));
+        assert_eq!((guides[1].from_x, guides[1].to_x, guides[1].to_height), (200.0, 400.0, 200.0));
+
+        // Off-center margins, or no parent, produce no guide
+        let moving = (210.0, 120.0, 310.0, 170.0);
+        assert!(calculate_spacing_guides(moving, &[], frame, 0.5).is_empty());
+        assert!(calculate_spacing_guides((200.0, 120.0, 300.0, 170.0), &[], None, 0.5).is_empty());
+    }
+
+    #[test]
+    fn test_distance_measurements_always_measure_parent() {
+        let moving = (100.0, 100.0, 150.0, 150.0);
+        let siblings = [(0.0, 100.0, 50.0, 150.0)];
+        let parent = Some((-20.0, 0.0, 400.0, 400.0));
+
+        // Fallback mode: left side reports only the sibling
+        let fallback = calculate_distance_measurements(moving, &siblings, parent, false);
+        let left: Vec<f32> = fallback
+            .iter()
+            .filter(|m| m.direction == 0 && m.to_x < m.from_x)
+            .map(|m| m.distance)
+            .collect();
+        assert_eq!(left, vec![50.0]);
+
+        // Always mode: left side reports the sibling and the parent edge
+        let always = calculate_distance_measurements(moving, &siblings, parent, true);
+        let left: Vec<f32> = always
+            .iter()
+            .filter(|m| m.direction == 0 && m.to_x < m.from_x)
+            .map(|m| m.distance)
+            .collect();
+        assert_eq!(left, vec![50.0, 120.0]);
+        assert_eq!(always.len(), fallback.len() + 1);
+    }
+
+    #[test]
+    fn test_distance_measurements_parent_nearer_than_sibling() {
+        // Moving node 10 units inside the frame's left edge, sibling far beyond it
+        let moving = (110.0, 100.0, 150.0, 150.0);
+        let siblings = [(0.0, 100.0, 20.0, 150.0)];
+        let parent = Some((100.0, 0.0, 400.0, 400.0));
+
+        let left: Vec<(f32, f32)> = calculate_distance_measurements(moving, &siblings, parent, false)
+            .iter()
+            .filter(|m| m.direction == 0 && m.to_x < m.from_x)
+            .map(|m| (m.to_x, m.distance))
+            .collect();
+        assert_eq!(left, vec![(100.0, 10.0)]);
+    }
+
+    #[test]
+    fn test_audit_spacing() {
+        let nodes = [
+            (1, (0.0, 0.0, 10.0, 10.0)),
+            (2, (30.0, 0.0, 40.0, 10.0)),  // 20 right of 1
+            (3, (50.0, 5.0, 60.0, 15.0)),  // 10 right of 2, overlapping vertically
+            (4, (20.0, 10.0, 30.0, 20.0)), // only touches 1's and 2's rows: not adjacent
+            (5, (0.0, 40.0, 10.0, 50.0)),  // 30 below 1
+        ];
+
+        let gaps = audit_spacing(&nodes);
+        assert_eq!(
+            gaps,
+            vec![
+                (1, 2, SPACING_HORIZONTAL, 20.0),
+                (1, 5, SPACING_VERTICAL, 30.0),
+                (2, 3, SPACING_HORIZONTAL, 10.0),
+                (4, 3, SPACING_HORIZONTAL, 20.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_point_measurements() {
+        let bounds = (100.0, 100.0, 200.0, 150.0);
+
+        // Point to the right of and below the node
+        let measurements = calculate_point_measurements(bounds, (230.0, 170.0));
+        assert_eq!(measurements.len(), 2);
+        assert_eq!(measurements[0].from_x, 200.0);
+        assert_eq!(measurements[0].from_y, 150.0);
+        assert_eq!(measurements[0].distance, 30.0);
+        assert_eq!((measurements[0].label_x(), measurements[0].label_y()), (215.0, 150.0));
+        assert_eq!(measurements[1].from_y, 150.0);
+        assert_eq!(measurements[1].from_x, 200.0);
+        assert_eq!(measurements[1].distance, 20.0);
+
+        // Point on the left edge only measures vertically
+        let measurements = calculate_point_measurements(bounds, (100.0, 110.0));
+        assert_eq!(measurements.len(), 1);
+        assert_eq!(measurements[0].direction, 1);
+        assert_eq!(measurements[0].distance, 10.0);
+    }
+
+    #[test]
+    fn test_compute_smart_guides_cross_alignment() {
+        let moving = (40.0, 40.0, 60.0, 60.0);
+        let others = [
+            // Centered on the moving node
+            (0.0, 0.0, 100.0, 100.0),
+            // Shares the center x only
+            (45.0, 300.0, 55.0, 310.0),
+        ];
+
+        let result = compute_smart_guides(moving, &others, 1.0, DEFAULT_SPACING_TOLERANCE, None, false);
+        let cross = result.cross_alignment().expect("centered node forms a cross");
+        assert_eq!((cross.x, cross.y), (50.0, 50.0));
+        assert_eq!(cross.node_count, 1);
+        assert!(result
+            .alignment_guides()
+            .iter()
+            .any(|g| g.alignment_type == CENTER_X && g.position == 50.0));
+
+        // Center x alone is not a cross
+        let result = compute_smart_guides(moving, &others[1..], 1.0, DEFAULT_SPACING_TOLERANCE, None, false);
+        assert!(result.cross_alignment().is_none());
+    }
+
+    #[test]
+    fn test_viewport_guides() {
+        let viewport = (-400.0, -300.0, 400.0, 300.0);
+
+        // Centered horizontally, top edge near the viewport top
+        let guides = calculate_viewport_guides((-50.5, -299.5, 50.0, -200.0), viewport, 1.0);
+        let summary: Vec<(u8, u8, f32)> = guides
+            .iter()
+            .map(|g| (g.guide_type, g.alignment_type, g.position))
+            .collect();
+        assert_eq!(summary, vec![(0, CENTER_X, -0.25), (1, EDGE_TOP, -299.5)]);
+        assert!(guides.iter().all(|g| g.viewport && g.node_count == 0));
+        assert_eq!(guides[0].span, 600.0);
+
+        assert!(calculate_viewport_guides((10.0, 10.0, 20.0, 20.0), viewport, 1.0).is_empty());
+    }
+
+    #[test]
+    fn test_is_aligned() {
+        let a = (0.0, 0.0, 100.0, 50.0);
+        let b = (0.5, 200.0, 40.0, 250.0);
+
+        assert!(is_aligned(a, b, EDGE_LEFT, 1.0));
+        assert!(is_aligned(a, b, EDGE_LEFT, 0.5));
+        assert!(!is_aligned(a, b, EDGE_LEFT, 0.4));
+        assert!(!is_aligned(a, b, EDGE_RIGHT, 1.0));
+        assert!(is_aligned(a, (30.0, 0.0, 70.0, 10.0), CENTER_X, 0.1));
+        assert!(!is_aligned(a, a, 42, 1.0));
+
+        // Threshold 0 still reports exact alignment
+        assert!(is_aligned(a, (0.0, 300.0, 10.0, 310.0), EDGE_LEFT, 0.0));
+        assert!(!is_aligned(a, b, EDGE_LEFT, 0.0));
+    }
+}
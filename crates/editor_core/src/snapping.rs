@@ -0,0 +1,933 @@
+//! Snapping
+//!
+//! Candidate generation and selection for point and box snapping.
+//! Targets are grid lines, edges/centers of nearby nodes, and virtual
+//! guide rects (group bounds, artboards) that are not nodes.
+
+use wasm_bindgen::prelude::*;
+
+// ============================================================================
+// Data Structures
+// ============================================================================
+
+#[wasm_bindgen]
+pub struct SnapResult {
+    snapped: bool,
+    x: f32,
+    y: f32,
+    guide_count: usize,
+    ambiguous_x: bool, // another candidate at a different position tied on x
+    ambiguous_y: bool,
+    alternate_x: f32, // tied alternative on x, or `x` when not ambiguous
+    alternate_y: f32,
+    strength_x: f32, // 1 - distance / threshold on x, 0 when x didn't snap
+    strength_y: f32,
+}
+
+#[wasm_bindgen]
+impl SnapResult {
+    #[wasm_bindgen(getter)]
+    pub fn snapped(&self) -> bool {
+        self.snapped
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn x(&self) -> f32 {
+        self.x
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn y(&self) -> f32 {
+        self.y
+    }
+
+    /// Number of snap candidates within threshold on either axis (grid lines
+    /// and node edges/centers), not only the ones that won
+    #[wasm_bindgen(getter)]
+    pub fn guide_count(&self) -> usize {
+        self.guide_count
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn ambiguous_x(&self) -> bool {
+        self.ambiguous_x
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn ambiguous_y(&self) -> bool {
+        self.ambiguous_y
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn alternate_x(&self) -> f32 {
+        self.alternate_x
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn alternate_y(&self) -> f32 {
+        self.alternate_y
+    }
+
+    /// Snap strength on x: `1 - |delta| / threshold`, so 1 is an exact snap and
+    /// values near 0 are at the threshold edge. 0 when x didn't snap.
+    #[wasm_bindgen(getter)]
+    pub fn strength_x(&self) -> f32 {
+        self.strength_x
+    }
+
+    /// Snap strength on y, as `strength_x`
+    #[wasm_bindgen(getter)]
+    pub fn strength_y(&self) -> f32 {
+        self.strength_y
+    }
+}
+
+/// Result of snapping a whole box: the applied delta and the snapped rect
+/// Moving is rigid, so width and height always match the input box
+#[wasm_bindgen]
+pub struct BoundsSnapResult {
+    snapped: bool,
+    dx: f32,
+    dy: f32,
+    min_x: f32,
+    min_y: f32,
+    max_x: f32,
+    max_y: f32,
+}
+
+#[wasm_bindgen]
+impl BoundsSnapResult {
+    #[wasm_bindgen(getter)]
+    pub fn snapped(&self) -> bool {
+        self.snapped
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn dx(&self) -> f32 {
+        self.dx
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn dy(&self) -> f32 {
+        self.dy
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn min_x(&self) -> f32 {
+        self.min_x
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn min_y(&self) -> f32 {
+        self.min_y
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn max_x(&self) -> f32 {
+        self.max_x
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn max_y(&self) -> f32 {
+        self.max_y
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> f32 {
+        self.max_x - self.min_x
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> f32 {
+        self.max_y - self.min_y
+    }
+}
+
+/// Result of snapping the moving corner of a box being resized
+/// `matched_width`/`matched_height` are the sibling dimensions matched, or the
+/// resulting dimension when that axis didn't size-snap. `min_x`..`max_y` is the
+/// resulting box with the fixed anchor held in place.
+#[wasm_bindgen]
+pub struct ResizeSnapResult {
+    snapped: bool,
+    x: f32,
+    y: f32,
+    width_matched: bool,
+    height_matched: bool,
+    matched_width: f32,
+    matched_height: f32,
+    min_x: f32,
+    min_y: f32,
+    max_x: f32,
+    max_y: f32,
+}
+
+#[wasm_bindgen]
+impl ResizeSnapResult {
+    #[wasm_bindgen(getter)]
+    pub fn snapped(&self) -> bool {
+        self.snapped
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn x(&self) -> f32 {
+        self.x
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn y(&self) -> f32 {
+        self.y
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn width_matched(&self) -> bool {
+        self.width_matched
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn height_matched(&self) -> bool {
+        self.height_matched
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn matched_width(&self) -> f32 {
+        self.matched_width
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn matched_height(&self) -> f32 {
+        self.matched_height
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn min_x(&self) -> f32 {
+        self.min_x
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn min_y(&self) -> f32 {
+        self.min_y
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn max_x(&self) -> f32 {
+        self.max_x
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn max_y(&self) -> f32 {
+        self.max_y
+    }
+}
+
+/// Snapping policy shared by the snap entry points
+pub(crate) struct SnapOptions {
+    pub threshold: f32,
+    pub grid_size: f32,
+    pub grid_x: bool,
+    pub grid_y: bool,
+    pub objects: bool,
+    pub axis_lock: u8, // AXIS_LOCK_*: locked axis passes through unsnapped
+    pub exclude_handle: Option<u32>, // node never used as a target (e.g. the one being dragged)
+    pub target_mask: u32,  // object targets need `flags & target_mask == target_match`
+    pub target_match: u32, // (mask 0 accepts every node; bits outside the mask are ignored)
+}
+
+impl SnapOptions {
+    /// Whether snapping on `axis` is suppressed by the axis lock
+    pub(crate) fn is_locked(&self, axis: u8) -> bool {
+        match axis {
+            AXIS_X => self.axis_lock == AXIS_LOCK_X,
+            _ => self.axis_lock == AXIS_LOCK_Y,
+        }
+    }
+
+    /// Whether a node with these flags may be an object snap target
+    /// Match bits outside the mask are ignored, so a match without a mask accepts every node
+    pub(crate) fn is_target(&self, handle: u32, flags: u32) -> bool {
+        Some(handle) != self.exclude_handle
+            && (self.target_mask == 0 || flags & self.target_mask == self.target_match & self.target_mask)
+    }
+}
+
+/// A rotated node as a snap target: its center, unrotated size and rotation (radians)
+#[derive(Clone, Copy)]
+pub(crate) struct OrientedRect {
+    pub cx: f32,
+    pub cy: f32,
+    pub width: f32,
+    pub height: f32,
+    pub rotation: f32,
+}
+
+impl OrientedRect {
+    /// Corners in winding order
+    pub(crate) fn corners(&self) -> [(f32, f32); 4] {
+        let (sin, cos) = self.rotation.sin_cos();
+        let (hw, hh) = (self.width / 2.0, self.height / 2.0);
+        [(-hw, -hh), (hw, -hh), (hw, hh), (-hw, hh)]
+            .map(|(lx, ly)| (self.cx + lx * cos - ly * sin, self.cy + lx * sin + ly * cos))
+    }
+}
+
+/// A position a coordinate could snap to
+#[derive(Clone, Copy)]
+pub(crate) struct SnapCandidate {
+    pub axis: u8,
+    pub position: f32,
+    pub source: u8,
+    pub distance: f32,
+}
+
+// ============================================================================
+// Candidate Constants
+// ============================================================================
+
+pub(crate) const AXIS_X: u8 = 0;
+pub(crate) const AXIS_Y: u8 = 1;
+
+pub(crate) const AXIS_LOCK_NONE: u8 = 0;
+pub(crate) const AXIS_LOCK_X: u8 = 1;
+pub(crate) const AXIS_LOCK_Y: u8 = 2;
+
+pub(crate) const SOURCE_GRID: u8 = 0;
+pub(crate) const SOURCE_EDGE: u8 = 1;
+pub(crate) const SOURCE_CENTER: u8 = 2;
+pub(crate) const SOURCE_GUIDE: u8 = 3;
+pub(crate) const SOURCE_SIZE: u8 = 4;
+
+/// Distance difference (world units) under which two candidates count as tied
+pub(crate) const SNAP_TIE_EPSILON: f32 = 1e-3;
+
+// ============================================================================
+// Snapping Calculations
+// ============================================================================
+
+/// Generate snap candidates for a point
+///
+/// Candidates strictly within `options.threshold` of the point on their axis
+/// are returned in generation order: node edges/centers, then rotated node
+/// edges/centers, then virtual guide rect edges/centers, then grid lines.
+///
+/// A rotated node's edge candidates on x are where its edges cross the point's
+/// y (none when the point is above or below the node), and likewise on y; its
+/// center snaps as usual.
+pub(crate) fn point_candidates(
+    point: (f32, f32),
+    node_bounds: &[(f32, f32, f32, f32)],  // [(min_x, min_y, max_x, max_y), ...]
+    oriented: &[OrientedRect],             // rotated nodes
+    guide_bounds: &[(f32, f32, f32, f32)], // virtual targets
+    options: &SnapOptions,
+) -> Vec<SnapCandidate> {
+    let mut candidates = Vec::new();
+    let (x, y) = point;
+
+    let mut consider = |axis: u8, position: f32, source: u8| {
+        if options.is_locked(axis) {
+            return;
+        }
+        let distance = match axis {
+            AXIS_X => (x - position).abs(),
+            _ => (y - position).abs(),
+        };
+        if distance < options.threshold {
+            candidates.push(SnapCandidate { axis, position, source, distance });
+        }
+    };
+
+    // Object snapping
+    if options.objects {
+        let targets = node_bounds
+            .iter()
+            .map(|&b| (b, SOURCE_EDGE))
+            .chain(guide_bounds.iter().map(|&b| (b, SOURCE_GUIDE)));
+
+        for (bounds, edge_source) in targets {
+            let center_source = if edge_source == SOURCE_GUIDE { SOURCE_GUIDE } else { SOURCE_CENTER };
+
+            // Snap to edges and center
+            consider(AXIS_X, bounds.0, edge_source);
+            consider(AXIS_X, (bounds.0 + bounds.2) / 2.0, center_source);
+            consider(AXIS_X, bounds.2, edge_source);
+            consider(AXIS_Y, bounds.1, edge_source);
+            consider(AXIS_Y, (bounds.1 + bounds.3) / 2.0, center_source);
+            consider(AXIS_Y, bounds.3, edge_source);
+        }
+
+        for rect in oriented {
+            let corners = rect.corners();
+            for i in 0..4 {
+                let (p0, p1) = (corners[i], corners[(i + 1) % 4]);
+                if let Some(edge_x) = edge_crossing(p0, p1, y) {
+                    consider(AXIS_X, edge_x, SOURCE_EDGE);
+                }
+                let (q0, q1) = ((p0.1, p0.0), (p1.1, p1.0));
+                if let Some(edge_y) = edge_crossing(q0, q1, x) {
+                    consider(AXIS_Y, edge_y, SOURCE_EDGE);
+                }
+            }
+            consider(AXIS_X, rect.cx, SOURCE_CENTER);
+            consider(AXIS_Y, rect.cy, SOURCE_CENTER);
+        }
+    }
+
+    // Grid snapping
+    if options.grid_size > 0.0 {
+        if options.grid_x {
+            consider(AXIS_X, (x / options.grid_size).round() * options.grid_size, SOURCE_GRID);
+        }
+        if options.grid_y {
+            consider(AXIS_Y, (y / options.grid_size).round() * options.grid_size, SOURCE_GRID);
+        }
+    }
+
+    candidates
+}
+
+/// x where the segment p0-p1 crosses height `y`; None when `y` is outside the
+/// segment's span or the segment is horizontal (its ends lie on the
+/// neighbouring edges)
+fn edge_crossing(p0: (f32, f32), p1: (f32, f32), y: f32) -> Option<f32> {
+    let dy = p1.1 - p0.1;
+    if dy.abs() <= f32::EPSILON {
+        return None;
+    }
+    let t = (y - p0.1) / dy;
+    (0.0..=1.0).contains(&t).then_some(p0.0 + (p1.0 - p0.0) * t)
+}
+
+/// Pick the nearest candidate on an axis (earliest wins ties)
+pub(crate) fn nearest_candidate(candidates: &[SnapCandidate], axis: u8) -> Option<SnapCandidate> {
+    nearest_candidate_index(candidates, axis).map(|i| candidates[i])
+}
+
+/// Index of the candidate `nearest_candidate` picks on an axis
+pub(crate) fn nearest_candidate_index(candidates: &[SnapCandidate], axis: u8) -> Option<usize> {
+    candidates
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| c.axis == axis)
+        .fold(None, |best: Option<(usize, f32)>, (i, c)| match best {
+            Some((_, d)) if d <= c.distance => best,
+            _ => Some((i, c.distance)),
+        })
+        .map(|(i, _)| i)
+}
+
+/// Find a candidate tied with `chosen` (distance within `SNAP_TIE_EPSILON`)
+/// that snaps to a different position
+pub(crate) fn tied_alternative(candidates: &[SnapCandidate], chosen: &SnapCandidate) -> Option<f32> {
+    candidates
+        .iter()
+        .find(|c| {
+            c.axis == chosen.axis
+                && (c.distance - chosen.distance).abs() <= SNAP_TIE_EPSILON
+                && (c.position - chosen.position).abs() > SNAP_TIE_EPSILON
+        })
+        .map(|c| c.position)
+}
+
+/// Snap a point to the nearest candidate on each axis
+/// `threshold` is the one the candidates were generated with, used for strengths
+pub(crate) fn snap_point(point: (f32, f32), candidates: &[SnapCandidate], threshold: f32) -> SnapResult {
+    let best_x = nearest_candidate(candidates, AXIS_X);
+    let best_y = nearest_candidate(candidates, AXIS_Y);
+
+    let x = best_x.map_or(point.0, |c| c.position);
+    let y = best_y.map_or(point.1, |c| c.position);
+    let alternate_x = best_x.and_then(|c| tied_alternative(candidates, &c));
+    let alternate_y = best_y.and_then(|c| tied_alternative(candidates, &c));
+    let strength = |best: Option<SnapCandidate>| best.map_or(0.0, |c| 1.0 - c.distance / threshold);
+
+    SnapResult {
+        snapped: best_x.is_some() || best_y.is_some(),
+        x,
+        y,
+        guide_count: candidates.len(),
+        ambiguous_x: alternate_x.is_some(),
+        ambiguous_y: alternate_y.is_some(),
+        alternate_x: alternate_x.unwrap_or(x),
+        alternate_y: alternate_y.unwrap_or(y),
+        strength_x: strength(best_x),
+        strength_y: strength(best_y),
+    }
+}
+
+/// Generate size-matching candidates for a resize
+///
+/// For each node size, the corner position that would give the box (spanned by
+/// the fixed `anchor` and the moving `corner`) that width or height, on the
+/// side the corner is already on. Kept if strictly within `options.threshold`.
+pub(crate) fn size_candidates(
+    anchor: (f32, f32),
+    corner: (f32, f32),
+    sizes: &[(f32, f32)], // [(width, height), ...], unrotated
+    options: &SnapOptions,
+) -> Vec<SnapCandidate> {
+    let mut candidates = Vec::new();
+    let sign = |v: f32| if v < 0.0 { -1.0 } else { 1.0 };
+    let sign_x = sign(corner.0 - anchor.0);
+    let sign_y = sign(corner.1 - anchor.1);
+
+    for &(width, height) in sizes {
+        let targets = [
+            (AXIS_X, corner.0, anchor.0 + sign_x * width),
+            (AXIS_Y, corner.1, anchor.1 + sign_y * height),
+        ];
+        for (axis, current, position) in targets {
+            let distance = (current - position).abs();
+            if !options.is_locked(axis) && distance < options.threshold {
+                candidates.push(SnapCandidate { axis, position, source: SOURCE_SIZE, distance });
+            }
+        }
+    }
+
+    candidates
+}
+
+/// Snap the moving corner of a resize to the nearest edge or size candidate per axis
+pub(crate) fn snap_resize(anchor: (f32, f32), corner: (f32, f32), candidates: &[SnapCandidate]) -> ResizeSnapResult {
+    let best_x = nearest_candidate(candidates, AXIS_X);
+    let best_y = nearest_candidate(candidates, AXIS_Y);
+
+    let x = best_x.map_or(corner.0, |c| c.position);
+    let y = best_y.map_or(corner.1, |c| c.position);
+
+    ResizeSnapResult {
+        snapped: best_x.is_some() || best_y.is_some(),
+        x,
+        y,
+        width_matched: best_x.is_some_and(|c| c.source == SOURCE_SIZE),
+        height_matched: best_y.is_some_and(|c| c.source == SOURCE_SIZE),
+        matched_width: (x - anchor.0).abs(),
+        matched_height: (y - anchor.1).abs(),
+        min_x: x.min(anchor.0),
+        min_y: y.min(anchor.1),
+        max_x: x.max(anchor.0),
+        max_y: y.max(anchor.1),
+    }
+}
+
+/// How dragging one of a box's resize anchors moves it
+///
+/// Anchors run clockwise from the top-left corner: 0 = top-left, 1 = top,
+/// 2 = top-right, 3 = right, 4 = bottom-right, 5 = bottom, 6 = bottom-left,
+/// 7 = left, 8 = center. Corners resize both axes and edges one (the other
+/// axis is locked) about the opposite anchor; the center moves the box.
+pub(crate) struct ResizeDrag {
+    anchor: u8,
+    bounds: (f32, f32, f32, f32),
+    pub fixed: (f32, f32),  // opposite anchor, held in place (box center for ANCHOR_CENTER)
+    pub moving: (f32, f32), // dragged anchor before snapping
+    pub axis_lock: u8,
+}
+
+pub(crate) const ANCHOR_CENTER: u8 = 8;
+
+/// Side each anchor drags per axis: Some(true) = max side, Some(false) = min side,
+/// None = axis not resized
+const ANCHOR_SIDES: [[Option<bool>; 2]; 8] = [
+    [Some(false), Some(false)],
+    [None, Some(false)],
+    [Some(true), Some(false)],
+    [Some(true), None],
+    [Some(true), Some(true)],
+    [None, Some(true)],
+    [Some(false), Some(true)],
+    [Some(false), None],
+];
+
+impl ResizeDrag {
+    /// Drag `anchor` of `bounds` to `pointer`; None for an unknown anchor
+    pub(crate) fn new(bounds: (f32, f32, f32, f32), anchor: u8, pointer: (f32, f32)) -> Option<Self> {
+        let center = ((bounds.0 + bounds.2) / 2.0, (bounds.1 + bounds.3) / 2.0);
+        let sides = match anchor {
+            ANCHOR_CENTER => [None, None],
+            _ => *ANCHOR_SIDES.get(anchor as usize)?,
+        };
+
+        // (fixed, moving) coordinates on one axis
+        let axis = |side: Option<bool>, min: f32, max: f32, mid: f32, pointer: f32| match side {
+            Some(true) => (min, pointer),
+            Some(false) => (max, pointer),
+            None if anchor == ANCHOR_CENTER => (mid, pointer),
+            None => (mid, mid),
+        };
+        let (fixed_x, moving_x) = axis(sides[0], bounds.0, bounds.2, center.0, pointer.0);
+        let (fixed_y, moving_y) = axis(sides[1], bounds.1, bounds.3, center.1, pointer.1);
+
+        let axis_lock = match sides {
+            _ if anchor == ANCHOR_CENTER => AXIS_LOCK_NONE,
+            [None, _] => AXIS_LOCK_X,
+            [_, None] => AXIS_LOCK_Y,
+            _ => AXIS_LOCK_NONE,
+        };
+
+        Some(Self { anchor, bounds, fixed: (fixed_x, fixed_y), moving: (moving_x, moving_y), axis_lock })
+    }
+
+    /// Whether the drag resizes (false when the center anchor moves the box)
+    pub(crate) fn is_resize(&self) -> bool {
+        self.anchor != ANCHOR_CENTER
+    }
+
+    /// Complete a snap of the dragged anchor with the resulting box
+    pub(crate) fn resolve(&self, mut result: ResizeSnapResult) -> ResizeSnapResult {
+        let (min_x, min_y, max_x, max_y) = self.bounds;
+        if !self.is_resize() {
+            let (dx, dy) = (result.x - self.fixed.0, result.y - self.fixed.1);
+            (result.min_x, result.min_y, result.max_x, result.max_y) = (min_x + dx, min_y + dy, max_x + dx, max_y + dy);
+            (result.matched_width, result.matched_height) = (max_x - min_x, max_y - min_y);
+        } else if self.axis_lock == AXIS_LOCK_X {
+            (result.min_x, result.max_x, result.matched_width) = (min_x, max_x, max_x - min_x);
+        } else if self.axis_lock == AXIS_LOCK_Y {
+            (result.min_y, result.max_y, result.matched_height) = (min_y, max_y, max_y - min_y);
+        }
+        result
+    }
+}
+
+/// Scale a snap threshold with the size of the moving box
+///
+/// `threshold = base + size_factor * min(width, height)`, capped at `max_threshold`
+/// but never below `base`. Using the smaller side keeps long thin boxes from getting
+/// a large pull. `size_factor` 0 gives the constant `base` threshold.
+pub(crate) fn size_scaled_threshold(
+    base: f32,
+    bounds: (f32, f32, f32, f32), // (min_x, min_y, max_x, max_y)
+    size_factor: f32,
+    max_threshold: f32,
+) -> f32 {
+    let size = (bounds.2 - bounds.0).min(bounds.3 - bounds.1).max(0.0);
+    (base + size_factor * size).min(max_threshold).max(base)
+}
+
+/// Snap a box rigidly by its edges and center
+/// Each axis independently takes the nearest target within threshold
+///
+/// A rotated node's x targets are where its edges cross the box's top, center
+/// and bottom lines, plus its center; likewise on y (as in `point_candidates`).
+pub(crate) fn snap_box(
+    bounds: (f32, f32, f32, f32),          // (min_x, min_y, max_x, max_y)
+    node_bounds: &[(f32, f32, f32, f32)],  // [(min_x, min_y, max_x, max_y), ...]
+    oriented: &[OrientedRect],             // rotated nodes
+    guide_bounds: &[(f32, f32, f32, f32)], // virtual targets
+    options: &SnapOptions,
+) -> BoundsSnapResult {
+    let (min_x, min_y, max_x, max_y) = bounds;
+    let sources_x = [min_x, (min_x + max_x) / 2.0, max_x];
+    let sources_y = [min_y, (min_y + max_y) / 2.0, max_y];
+
+    // Best (delta, |delta|) per axis
+    let mut best_x: Option<(f32, f32)> = None;
+    let mut best_y: Option<(f32, f32)> = None;
+    let consider = |best: &mut Option<(f32, f32)>, source: f32, target: f32| {
+        let delta = target - source;
+        if delta.abs() < options.threshold && best.is_none_or(|(_, d)| delta.abs() < d) {
+            *best = Some((delta, delta.abs()));
+        }
+    };
+
+    // Object snapping
+    if options.objects {
+        for target in node_bounds.iter().chain(guide_bounds) {
+            let targets_x = [target.0, (target.0 + target.2) / 2.0, target.2];
+            let targets_y = [target.1, (target.1 + target.3) / 2.0, target.3];
+
+            for &sx in &sources_x {
+                for &tx in &targets_x {
+                    consider(&mut best_x, sx, tx);
+                }
+            }
+            for &sy in &sources_y {
+                for &ty in &targets_y {
+                    consider(&mut best_y, sy, ty);
+                }
+            }
+        }
+
+        for rect in oriented {
+            let corners = rect.corners();
+            let mut targets_x = vec![rect.cx];
+            let mut targets_y = vec![rect.cy];
+            for i in 0..4 {
+                let (p0, p1) = (corners[i], corners[(i + 1) % 4]);
+                let (q0, q1) = ((p0.1, p0.0), (p1.1, p1.0));
+                targets_x.extend(sources_y.iter().filter_map(|&sy| edge_crossing(p0, p1, sy)));
+                targets_y.extend(sources_x.iter().filter_map(|&sx| edge_crossing(q0, q1, sx)));
+            }
+
+            for &sx in &sources_x {
+                for &tx in &targets_x {
+                    consider(&mut best_x, sx, tx);
+                }
+            }
+            for &sy in &sources_y {
+                for &ty in &targets_y {
+                    consider(&mut best_y, sy, ty);
+                }
+            }
+        }
+    }
+
+    // Grid snapping
+    if options.grid_size > 0.0 {
+        let grid = |v: f32| (v / options.grid_size).round() * options.grid_size;
+        if options.grid_x {
+            for &sx in &sources_x {
+                consider(&mut best_x, sx, grid(sx));
+            }
+        }
+        if options.grid_y {
+            for &sy in &sources_y {
+                consider(&mut best_y, sy, grid(sy));
+            }
+        }
+    }
+
+    let best_x = best_x.filter(|_| !options.is_locked(AXIS_X));
+    let best_y = best_y.filter(|_| !options.is_locked(AXIS_Y));
+    let dx = best_x.map_or(0.0, |(delta, _)| delta);
+    let dy = best_y.map_or(0.0, |(delta, _)| delta);
+
+    BoundsSnapResult {
+        snapped: best_x.is_some() || best_y.is_some(),
+        dx,
+        dy,
+        min_x: min_x + dx,
+        min_y: min_y + dy,
+        max_x: max_x + dx,
+        max_y: max_y + dy,
+    }
+}
+
+// ============================================================================
+// Angle Constraints
+// ============================================================================
+
+/// Project `point` onto the nearest line through `anchor` at a multiple of
+/// `increment` radians (e.g. Shift-drawing at 45 degrees). Computed in f64 so
+/// axis-aligned results land exactly on the anchor's row or column.
+pub(crate) fn constrain_to_angle(anchor: (f32, f32), point: (f32, f32), increment: f64) -> (f32, f32) {
+    let (dx, dy) = ((point.0 - anchor.0) as f64, (point.1 - anchor.1) as f64);
+    if dx == 0.0 && dy == 0.0 {
+        return point;
+    }
+
+    let angle = (dy.atan2(dx) / increment).round() * increment;
+    let (sin, cos) = angle.sin_cos();
+    let distance = dx * cos + dy * sin;
+
+    // Drop the ~1e-17 residue of sin/cos at multiples of 90 degrees
+    let offset = |unit: f64| if unit.abs() < 1e-12 { 0.0 } else { distance * unit };
+    ((anchor.0 as f64 + offset(cos)) as f32, (anchor.1 as f64 + offset(sin)) as f32)
+}
+
+// ============================================================================
+// Output Rounding
+// ============================================================================
+
+/// Most decimals snap rounding keeps; f32 holds about 9 significant digits, so
+/// more would only round away noise that is not there
+pub(crate) const MAX_SNAP_DECIMALS: u32 = 9;
+
+/// Round to `decimals` places, computed in f64 so e.g. 99.99998 becomes exactly 100
+/// (results are still f32, so non-binary fractions like 0.1 are the nearest f32)
+pub(crate) fn round_to(value: f32, decimals: u32) -> f32 {
+    let factor = 10f64.powi(decimals as i32);
+    ((value as f64 * factor).round() / factor) as f32
+}
+
+impl SnapResult {
+    /// Round the returned coordinates to `decimals` places
+    pub(crate) fn rounded(mut self, decimals: u32) -> Self {
+        for value in [&mut self.x, &mut self.y, &mut self.alternate_x, &mut self.alternate_y] {
+            *value = round_to(*value, decimals);
+        }
+        self
+    }
+}
+
+impl BoundsSnapResult {
+    /// Round the returned box and deltas to `decimals` places
+    pub(crate) fn rounded(mut self, decimals: u32) -> Self {
+        for value in [
+            &mut self.dx,
+            &mut self.dy,
+            &mut self.min_x,
+            &mut self.min_y,
+            &mut self.max_x,
+            &mut self.max_y,
+        ] {
+            *value = round_to(*value, decimals);
+        }
+        self
+    }
+}
+
+impl ResizeSnapResult {
+    /// Round the returned corner, matched sizes and box to `decimals` places
+    pub(crate) fn rounded(mut self, decimals: u32) -> Self {
+        for value in [
+            &mut self.x,
+            &mut self.y,
+            &mut self.matched_width,
+            &mut self.matched_height,
+            &mut self.min_x,
+            &mut self.min_y,
+            &mut self.max_x,
+            &mut self.max_y,
+        ] {
+            *value = round_to(*value, decimals);
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(threshold: f32, grid_size: f32) -> SnapOptions {
+        SnapOptions {
+            threshold,
+            grid_size,
+            grid_x: true,
+            grid_y: true,
+            objects: true,
+            axis_lock: AXIS_LOCK_NONE,
+            exclude_handle: None,
+            target_mask: 0,
+            target_match: 0,
+        }
+    }
+
+    #[test]
+    fn test_point_candidates_sources() {
+        let nodes = [(0.0, 0.0, 100.0, 100.0)];
+        let candidates = point_candidates((52.0, 98.0), &nodes, &[], &[], &options(5.0, 0.0));
+
+        let summary: Vec<(u8, f32, u8)> = candidates
+            .iter()
+            .map(|c| (c.axis, c.position, c.source))
+            .collect();
+        assert_eq!(summary, vec![(AXIS_X, 50.0, SOURCE_CENTER), (AXIS_Y, 100.0, SOURCE_EDGE)]);
+    }
+
+    #[test]
+    fn test_point_candidates_rotated_edges() {
+        // 100x100 square rotated 45 degrees about the origin: corners on the axes
+        // at distance d, AABB edges at +-d
+        let d = 50.0 * std::f32::consts::SQRT_2;
+        let square = [OrientedRect {
+            cx: 0.0,
+            cy: 0.0,
+            width: 100.0,
+            height: 100.0,
+            rotation: std::f32::consts::FRAC_PI_4,
+        }];
+
+        // Halfway up the lower-right edge, the visual edge is at x = d / 2
+        let point = (d / 2.0 + 2.0, d / 2.0);
+        let candidates = point_candidates(point, &[], &square, &[], &options(5.0, 0.0));
+        let result = snap_point(point, &candidates, 5.0);
+        assert!((result.x - d / 2.0).abs() < 1e-3);
+
+        // The AABB's edge at y = d / 2 is not a candidate there
+        let point = (d - 2.0, d / 2.0);
+        let candidates = point_candidates(point, &[], &square, &[], &options(5.0, 0.0));
+        assert!(!candidates.iter().any(|c| c.axis == AXIS_X));
+
+        // At the right corner the edges meet the AABB extent
+        let point = (d - 1.0, 0.0);
+        let candidates = point_candidates(point, &[], &square, &[], &options(5.0, 0.0));
+        let result = snap_point(point, &candidates, 5.0);
+        assert!((result.x - d).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_snap_point_nearest_wins() {
+        // Grid line at 100 is 3 away, node edge at 101 is 2 away
+        let nodes = [(101.0, 0.0, 200.0, 50.0)];
+        let candidates = point_candidates((103.0, 500.0), &nodes, &[], &[], &options(5.0, 50.0));
+        let result = snap_point((103.0, 500.0), &candidates, 5.0);
+
+        assert_eq!(result.x, 101.0);
+        assert_eq!(result.y, 500.0);
+        assert!(!result.ambiguous_x);
+
+        // 2 of 5 units away on x; y already sits on a grid line
+        assert!((result.strength_x - 0.6).abs() < 1e-6);
+        assert_eq!(result.strength_y, 1.0);
+    }
+
+    #[test]
+    fn test_snap_point_reports_ties() {
+        // Point exactly between two edges 4 units apart
+        let nodes = [(0.0, 0.0, 98.0, 10.0), (102.0, 0.0, 200.0, 10.0)];
+        let candidates = point_candidates((100.0, 300.0), &nodes, &[], &[], &options(5.0, 0.0));
+        let result = snap_point((100.0, 300.0), &candidates, 5.0);
+
+        assert!(result.ambiguous_x);
+        assert_eq!(result.x, 98.0);
+        assert_eq!(result.alternate_x, 102.0);
+        assert!(!result.ambiguous_y);
+        assert_eq!(result.alternate_y, result.y);
+        assert_eq!(result.strength_y, 0.0);
+
+        // Two nodes sharing the same edge are not ambiguous
+        let nodes = [(0.0, 0.0, 98.0, 10.0), (0.0, 20.0, 98.0, 30.0)];
+        let candidates = point_candidates((100.0, 300.0), &nodes, &[], &[], &options(5.0, 0.0));
+        assert!(!snap_point((100.0, 300.0), &candidates, 5.0).ambiguous_x);
+    }
+
+    #[test]
+    fn test_constrain_to_angle() {
+        let diagonal = std::f64::consts::FRAC_PI_4;
+
+        // Near-horizontal snaps onto the anchor's row
+        assert_eq!(constrain_to_angle((10.0, 10.0), (50.0, 13.0), diagonal), (50.0, 10.0));
+        // Near-diagonal projects onto the 45 degree line
+        let (x, y) = constrain_to_angle((0.0, 0.0), (10.0, 12.0), diagonal);
+        assert!((x - 11.0).abs() < 1e-4 && (y - 11.0).abs() < 1e-4);
+        // Up-left stays up-left
+        let (x, y) = constrain_to_angle((0.0, 0.0), (-20.0, -19.0), diagonal);
+        assert!((x + 19.5).abs() < 1e-4 && (y + 19.5).abs() < 1e-4);
+        // Degenerate: the point itself
+        assert_eq!(constrain_to_angle((5.0, 5.0), (5.0, 5.0), diagonal), (5.0, 5.0));
+    }
+
+    #[test]
+    fn test_round_to() {
+        assert_eq!(round_to(99.99998, 0), 100.0);
+        assert_eq!(round_to(12.3456, 2), 12.35);
+        assert_eq!(round_to(-0.4999, 3), -0.5);
+    }
+
+    #[test]
+    fn test_size_scaled_threshold() {
+        let small = (0.0, 0.0, 10.0, 40.0);
+        let large = (0.0, 0.0, 200.0, 100.0);
+
+        assert_eq!(size_scaled_threshold(4.0, large, 0.0, f32::INFINITY), 4.0);
+        assert_eq!(size_scaled_threshold(4.0, small, 0.1, f32::INFINITY), 5.0);
+        assert_eq!(size_scaled_threshold(4.0, large, 0.1, f32::INFINITY), 14.0);
+        assert_eq!(size_scaled_threshold(4.0, large, 0.1, 12.0), 12.0);
+        assert_eq!(size_scaled_threshold(4.0, large, 0.1, 2.0), 4.0);
+    }
+
+    #[test]
+    fn test_size_candidates_follow_drag_direction() {
+        let nodes = [(80.0, 30.0)];
+
+        // Dragging right/down from (0, 0)
+        let candidates = size_candidates((0.0, 0.0), (78.0, 90.0), &nodes, &options(5.0, 0.0));
+        let summary: Vec<(u8, f32)> = candidates.iter().map(|c| (c.axis, c.position)).collect();
+        assert_eq!(summary, vec![(AXIS_X, 80.0)]);
+
+        // Dragging left/up from (0, 0)
+        let candidates = size_candidates((0.0, 0.0), (-78.0, -32.0), &nodes, &options(5.0, 0.0));
+        let summary: Vec<(u8, f32)> = candidates.iter().map(|c| (c.axis, c.position)).collect();
+        assert_eq!(summary, vec![(AXIS_X, -80.0), (AXIS_Y, -30.0)]);
+    }
+}
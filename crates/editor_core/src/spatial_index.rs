@@ -3,39 +3,227 @@
 //! High-performance spatial indexing for dynamic scenes with frequent updates.
 //! Uses a uniform grid with hash-based lookup for O(1) insertion/removal
 //! and efficient spatial queries.
+//!
+//! The handle-to-slot and `grid` maps use FxHash: it is much faster than SipHash for
+//! small integer keys and has a fixed seed, so iteration order is stable from
+//! run to run. Query result *sets* are unaffected; only their order becomes
+//! reproducible.
+//!
+//! Cells holding more than `SUBGRID_THRESHOLD` entries (e.g. a dense dot plot)
+//! get a nested finer grid so point queries into them scan a small bucket
+//! instead of the whole cell.
+//!
+//! Pinned nodes (e.g. an infinite background) are kept in their own list that
+//! area queries never enumerate; only point queries and bounds lookups see them.
+//!
+//! With the `parallel` feature, `query_rect` over many cells partitions the
+//! cells across rayon threads. Builds without it use the single-threaded path.
+
+use crate::utils::{map_bytes, vec_bytes};
+use rustc_hash::FxHashMap;
+use std::cmp::Ordering;
+use std::sync::Mutex;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// Default cell width and height in world units
+const DEFAULT_CELL_SIZE: f32 = 256.0;
+
+/// Minimum grid capacity so an index created empty doesn't rehash on every early insert
+const MIN_GRID_CAPACITY: usize = 64;
+
+/// Grid occupancy (cells / capacity) below which `gc` shrinks the grid map
+const GC_SHRINK_RATIO: f32 = 0.25;
+
+/// Cell span above which queries scan nodes/cells directly instead of enumerating the range
+const MAX_QUERY_CELLS: usize = 4096;
+
+/// Default cell span above which a node is kept in the overflow list
+const DEFAULT_OVERFLOW_CELL_THRESHOLD: usize = 64;
+
+/// Hard cap on the overflow threshold, so no node is ever spread over (and
+/// enumerates) more cells than this however the threshold is configured
+const MAX_NODE_CELLS: usize = 16_384;
+
+/// Cell entry count above which a cell gets a sub-grid
+const SUBGRID_THRESHOLD: usize = 128;
+
+/// Sub-grid buckets per cell axis
+const SUBGRID_DIVISIONS: usize = 8;
+
+/// Cell count from which `query_rect` splits the work across threads
+#[cfg(feature = "parallel")]
+const PARALLEL_MIN_CELLS: usize = 256;
+
+/// Per-slot stamp of the last query that visited each slot, used to dedupe
+/// nodes spanning several cells without allocating a set per query
+#[derive(Default)]
+struct VisitMarks {
+    stamps: Vec<u32>,
+    query_id: u32,
+}
+
+impl VisitMarks {
+    /// Start a new query over `slot_count` slots
+    fn begin(&mut self, slot_count: usize) {
+        if self.stamps.len() < slot_count {
+            self.stamps.resize(slot_count, 0);
+        }
+        self.query_id = self.query_id.wrapping_add(1);
+        if self.query_id == 0 {
+            // Wrapped: old stamps could collide with new ids
+            self.stamps.fill(0);
+            self.query_id = 1;
+        }
+    }
+
+    /// Mark a slot visited, returning false if the current query already saw it
+    fn first_visit(&mut self, slot: usize) -> bool {
+        let seen = self.stamps[slot] == self.query_id;
+        self.stamps[slot] = self.query_id;
+        !seen
+    }
+}
+
+/// Finer grid nested inside one crowded cell: `SUBGRID_DIVISIONS`^2 buckets of
+/// slots in row-major order. Slots stay in the cell's own list as well.
+struct SubGrid {
+    buckets: Vec<Vec<u32>>,
+}
+
+/// Inclusive bucket range (min_bx, min_by, max_bx, max_by) within a sub-grid
+type BucketRange = (usize, usize, usize, usize);
+
+impl SubGrid {
+    fn new() -> Self {
+        Self {
+            buckets: vec![Vec::new(); SUBGRID_DIVISIONS * SUBGRID_DIVISIONS],
+        }
+    }
 
-use std::collections::HashMap;
+    /// Buckets covered by a range, row-major
+    fn bucket_indices((min_bx, min_by, max_bx, max_by): BucketRange) -> impl Iterator<Item = usize> {
+        (min_by..=max_by).flat_map(move |by| (min_bx..=max_bx).map(move |bx| by * SUBGRID_DIVISIONS + bx))
+    }
 
-const GRID_CELL_SIZE: f32 = 256.0;
+    fn insert(&mut self, slot: u32, range: BucketRange) {
+        for b in Self::bucket_indices(range) {
+            self.buckets[b].push(slot);
+        }
+    }
 
-/// AABB bounds in SoA layout for cache efficiency
-#[derive(Clone)]
-struct NodeData {
-    min_x: f32,
-    min_y: f32,
-    max_x: f32,
-    max_y: f32,
-    z_index: i32,
+    fn remove(&mut self, slot: u32, range: BucketRange) {
+        for b in Self::bucket_indices(range) {
+            self.buckets[b].retain(|&s| s != slot);
+        }
+    }
 }
 
 /// Spatial index using uniform grid hashing
+///
+/// Node fields live in parallel arrays indexed by a dense slot so that query
+/// narrow-phases walk contiguous memory. Slots of removed nodes are recycled.
+/// Grid cells and the overflow and pinned lists store slots, not handles.
 pub struct SpatialIndex {
-    /// Node data stored in Structure of Arrays (SoA) layout
-    nodes: HashMap<u32, NodeData>,
+    /// Handle -> dense slot
+    slots: FxHashMap<u32, u32>,
+
+    /// Per-slot node data in Structure of Arrays (SoA) layout
+    handles: Vec<u32>,
+    min_x: Vec<f32>,
+    min_y: Vec<f32>,
+    max_x: Vec<f32>,
+    max_y: Vec<f32>,
+    z_index: Vec<f32>,
+    /// Node bypasses the grid and lives in the overflow list
+    in_overflow: Vec<bool>,
+    /// Node bypasses the grid and lives in the pinned list
+    in_pinned: Vec<bool>,
+    /// Slot holds a node (false once removed, until recycled)
+    live: Vec<bool>,
+
+    /// Removed slots available for reuse
+    free_slots: Vec<u32>,
     
-    /// Grid cells mapping to node handles
-    grid: HashMap<(i32, i32), Vec<u32>>,
+    /// Grid cells mapping to node slots
+    grid: FxHashMap<(i32, i32), Vec<u32>>,
+
+    /// Sub-grids of cells holding more than `SUBGRID_THRESHOLD` entries
+    sub_grids: FxHashMap<(i32, i32), SubGrid>,
+
+    /// Slots of nodes spanning too many cells; always tested by queries instead
+    overflow: Vec<u32>,
+
+    /// Slots of pinned nodes; skipped by area queries, tested by point queries
+    pinned: Vec<u32>,
+
+    /// Dedup marks for `query_rect` (locked per query; queries only take `&self`)
+    visited: Mutex<VisitMarks>,
+
+    /// Grid cell dimensions in world units (may differ per axis)
+    cell_width: f32,
+    cell_height: f32,
+
+    /// Cell span above which a node goes to the overflow list
+    overflow_cell_threshold: usize,
+
+    /// Sort z-ordered results topmost-first (true) or bottommost-first (false)
+    z_descending: bool,
 }
 
 impl SpatialIndex {
     pub fn new(capacity: usize) -> Self {
         Self {
-            nodes: HashMap::with_capacity(capacity),
-            grid: HashMap::with_capacity(capacity * 4),
+            slots: FxHashMap::with_capacity_and_hasher(capacity, Default::default()),
+            handles: Vec::with_capacity(capacity),
+            min_x: Vec::with_capacity(capacity),
+            min_y: Vec::with_capacity(capacity),
+            max_x: Vec::with_capacity(capacity),
+            max_y: Vec::with_capacity(capacity),
+            z_index: Vec::with_capacity(capacity),
+            in_overflow: Vec::with_capacity(capacity),
+            in_pinned: Vec::with_capacity(capacity),
+            live: Vec::with_capacity(capacity),
+            free_slots: Vec::new(),
+            grid: FxHashMap::with_capacity_and_hasher(
+                capacity.saturating_mul(4).max(MIN_GRID_CAPACITY),
+                Default::default(),
+            ),
+            sub_grids: FxHashMap::default(),
+            overflow: Vec::new(),
+            pinned: Vec::new(),
+            visited: Mutex::new(VisitMarks::default()),
+            cell_width: DEFAULT_CELL_SIZE,
+            cell_height: DEFAULT_CELL_SIZE,
+            overflow_cell_threshold: DEFAULT_OVERFLOW_CELL_THRESHOLD,
+            z_descending: true,
         }
     }
 
-    /// Insert or update a node
+    /// Set whether z-ordered queries return topmost-first (default) or bottommost-first
+    pub fn set_z_order_descending(&mut self, descending: bool) {
+        self.z_descending = descending;
+    }
+
+    /// Set the grid cell dimensions (e.g. wide cells for wide-and-short content),
+    /// re-indexing existing nodes. Non-positive or non-finite sizes are ignored.
+    pub fn set_cell_size(&mut self, width: f32, height: f32) {
+        if width > 0.0 && width.is_finite() && height > 0.0 && height.is_finite() {
+            self.cell_width = width;
+            self.cell_height = height;
+            self.rebuild();
+        }
+    }
+
+    /// Set the cell span above which nodes skip the grid, re-indexing existing nodes
+    /// Thresholds above `MAX_NODE_CELLS` are clamped to it.
+    pub fn set_overflow_cell_threshold(&mut self, threshold: usize) {
+        self.overflow_cell_threshold = threshold.min(MAX_NODE_CELLS);
+        self.rebuild();
+    }
+
+    /// Insert or update a node, keeping its pinned state (new nodes are unpinned)
     pub fn upsert(
         &mut self,
         handle: u32,
@@ -43,102 +231,322 @@ impl SpatialIndex {
         min_y: f32,
         max_x: f32,
         max_y: f32,
-        z_index: i32,
+        z_index: f32,
     ) {
-        // Remove old entry if exists
-        if self.nodes.contains_key(&handle) {
+        let pinned = self.is_pinned(handle);
+        self.upsert_placed(handle, (min_x, min_y, max_x, max_y), z_index, pinned);
+    }
+
+    /// Insert or update a node, pinning it (out of the grid) or placing it
+    /// in the grid or overflow list
+    pub fn upsert_placed(&mut self, handle: u32, bounds: (f32, f32, f32, f32), z_index: f32, pinned: bool) {
+        let (min_x, min_y, max_x, max_y) = bounds;
+        let overflow = !pinned && self.cell_span(min_x, min_y, max_x, max_y) > self.overflow_cell_threshold;
+
+        if let Some(&slot) = self.slots.get(&handle) {
+            // Update in place, touching only the cells entered or left
+            // (none for the common sub-cell drag)
+            let i = slot as usize;
+            if overflow == self.in_overflow[i] && pinned == self.in_pinned[i] {
+                if !overflow && !pinned {
+                    self.move_cells(slot, self.bounds_at(i), (min_x, min_y, max_x, max_y));
+                }
+                self.min_x[i] = min_x;
+                self.min_y[i] = min_y;
+                self.max_x[i] = max_x;
+                self.max_y[i] = max_y;
+                self.z_index[i] = z_index;
+                return;
+            }
+
+            // Overflow or pinned status flipped: re-insert from scratch
             self.remove(handle);
         }
 
         // Store node data
-        let node_data = NodeData {
-            min_x,
-            min_y,
-            max_x,
-            max_y,
-            z_index,
+        let slot = match self.free_slots.pop() {
+            Some(slot) => {
+                let i = slot as usize;
+                self.handles[i] = handle;
+                self.min_x[i] = min_x;
+                self.min_y[i] = min_y;
+                self.max_x[i] = max_x;
+                self.max_y[i] = max_y;
+                self.z_index[i] = z_index;
+                self.in_overflow[i] = overflow;
+                self.in_pinned[i] = pinned;
+                self.live[i] = true;
+                slot
+            }
+            None => {
+                self.handles.push(handle);
+                self.min_x.push(min_x);
+                self.min_y.push(min_y);
+                self.max_x.push(max_x);
+                self.max_y.push(max_y);
+                self.z_index.push(z_index);
+                self.in_overflow.push(overflow);
+                self.in_pinned.push(pinned);
+                self.live.push(true);
+                (self.handles.len() - 1) as u32
+            }
         };
 
-        // Compute grid cells this node overlaps
-        let cells = self.compute_cells(min_x, min_y, max_x, max_y);
+        if pinned {
+            self.pinned.push(slot);
+        } else if overflow {
+            self.overflow.push(slot);
+        } else {
+            // Compute grid cells this node overlaps
+            let cells = self.compute_cells(min_x, min_y, max_x, max_y);
 
-        // Add to grid cells
-        for cell in cells {
-            self.grid.entry(cell).or_insert_with(Vec::new).push(handle);
+            // Add to grid cells
+            for cell in cells {
+                self.cell_insert(cell, slot, (min_x, min_y, max_x, max_y));
+            }
         }
 
-        self.nodes.insert(handle, node_data);
+        self.slots.insert(handle, slot);
+    }
+
+    /// Shift a node's bounds by a delta and re-index it, preserving its z-index
+    /// Returns false for unknown handles
+    pub fn translate(&mut self, handle: u32, dx: f32, dy: f32) -> bool {
+        let slot = match self.slots.get(&handle) {
+            Some(&slot) => slot,
+            None => return false,
+        };
+        let i = slot as usize;
+        let old = self.bounds_at(i);
+
+        // Upsert updates in place, touching only the cells entered or left
+        self.upsert(handle, old.0 + dx, old.1 + dy, old.2 + dx, old.3 + dy, self.z_index[i]);
+        true
+    }
+
+    /// Change a node's z-index, leaving its cells untouched (cells hold slots,
+    /// and z is read per query). Returns false for unknown handles
+    pub fn set_z_index(&mut self, handle: u32, z_index: f32) -> bool {
+        match self.slots.get(&handle) {
+            Some(&slot) => {
+                self.z_index[slot as usize] = z_index;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Pin or unpin a node, moving it out of or back into the grid
+    /// Returns false for unknown handles
+    pub fn set_pinned(&mut self, handle: u32, pinned: bool) -> bool {
+        let Some(&slot) = self.slots.get(&handle) else {
+            return false;
+        };
+        let i = slot as usize;
+        if self.in_pinned[i] != pinned {
+            self.upsert_placed(handle, self.bounds_at(i), self.z_index[i], pinned);
+        }
+        true
     }
 
     /// Remove a node
     pub fn remove(&mut self, handle: u32) {
-        if let Some(node_data) = self.nodes.remove(&handle) {
+        if let Some(slot) = self.slots.remove(&handle) {
+            let i = slot as usize;
+            self.live[i] = false;
+            self.free_slots.push(slot);
+
+            if self.in_pinned[i] {
+                self.pinned.retain(|&s| s != slot);
+                return;
+            }
+            if self.in_overflow[i] {
+                self.overflow.retain(|&s| s != slot);
+                return;
+            }
+
             // Compute cells and remove from grid
-            let cells = self.compute_cells(
-                node_data.min_x,
-                node_data.min_y,
-                node_data.max_x,
-                node_data.max_y,
-            );
+            let cells = self.compute_cells(self.min_x[i], self.min_y[i], self.max_x[i], self.max_y[i]);
 
+            let bounds = self.bounds_at(i);
             for cell in cells {
-                if let Some(handles) = self.grid.get_mut(&cell) {
-                    handles.retain(|&h| h != handle);
-                    if handles.is_empty() {
-                        self.grid.remove(&cell);
-                    }
-                }
+                self.cell_remove(cell, slot, bounds);
             }
         }
     }
 
-    /// Query nodes at a point, returns sorted by z-index (topmost first)
+    /// Query nodes at a point, returns sorted by z-index (topmost first by default)
     pub fn query_point(&self, x: f32, y: f32) -> Vec<u32> {
         let cell = self.world_to_cell(x, y);
         
         let mut hits = Vec::new();
 
-        if let Some(handles) = self.grid.get(&cell) {
-            for &handle in handles {
-                if let Some(node) = self.nodes.get(&handle) {
-                    if x >= node.min_x && x <= node.max_x && y >= node.min_y && y <= node.max_y {
-                        hits.push((handle, node.z_index));
-                    }
-                }
+        // Crowded cells are narrowed to the sub-grid bucket holding the point
+        let cell_slots = match self.sub_grids.get(&cell) {
+            Some(sub_grid) => {
+                let (bx, by, _, _) = self.bucket_range(cell, (x, y, x, y));
+                sub_grid.buckets[by * SUBGRID_DIVISIONS + bx].as_slice()
+            }
+            None => self.grid.get(&cell).map(|slots| slots.as_slice()).unwrap_or(&[]),
+        };
+
+        for &slot in cell_slots.iter().chain(&self.overflow).chain(&self.pinned) {
+            let i = slot as usize;
+            if x >= self.min_x[i] && x <= self.max_x[i] && y >= self.min_y[i] && y <= self.max_y[i] {
+                hits.push((self.handles[i], self.z_index[i]));
             }
         }
 
-        // Sort by z-index descending (topmost first)
-        hits.sort_by(|a, b| b.1.cmp(&a.1));
+        // Sort by z-index in the configured direction
+        hits.sort_by(|&(_, a), &(_, b)| self.compare_z(a, b));
         hits.into_iter().map(|(h, _)| h).collect()
     }
 
+    /// Sort handles by z-index in the configured direction (unknown handles sort as lowest)
+    pub fn sort_by_z(&self, handles: &mut [u32]) {
+        let z = |handle: u32| self.get_z_index(handle).unwrap_or(f32::NAN);
+        handles.sort_by(|&a, &b| self.compare_z(z(a), z(b)));
+    }
+
     /// Query nodes within a rectangle
     pub fn query_rect(&self, min_x: f32, min_y: f32, max_x: f32, max_y: f32) -> Vec<u32> {
-        let cells = self.compute_cells(min_x, min_y, max_x, max_y);
         let mut candidates = Vec::new();
-        let mut seen = std::collections::HashSet::new();
-
-        for cell in cells {
-            if let Some(handles) = self.grid.get(&cell) {
-                for &handle in handles {
-                    if seen.insert(handle) {
-                        if let Some(node) = self.nodes.get(&handle) {
-                            // AABB intersection test
-                            if !(node.max_x < min_x
-                                || node.min_x > max_x
-                                || node.max_y < min_y
-                                || node.min_y > max_y)
-                            {
-                                candidates.push(handle);
-                            }
-                        }
+        self.query_rect_into(min_x, min_y, max_x, max_y, &mut candidates);
+        candidates
+    }
+
+    /// Query nodes within a rectangle, appending to `out`
+    /// Does not allocate beyond growing `out` (unless the `parallel` path is taken)
+    pub fn query_rect_into(&self, min_x: f32, min_y: f32, max_x: f32, max_y: f32, out: &mut Vec<u32>) {
+        // Nothing to find: skip enumerating cells (e.g. camera set before nodes load)
+        if self.is_empty() {
+            return;
+        }
+
+        let rect = (min_x, min_y, max_x, max_y);
+        let span = self.cell_span(min_x, min_y, max_x, max_y);
+
+        // Enumerating a huge cell range (extreme zoom-out) costs more than
+        // testing every node, so fall back to a linear scan of the packed arrays
+        if span > MAX_QUERY_CELLS {
+            out.extend(
+                (0..self.handles.len())
+                    .filter(|&i| self.live[i] && !self.in_pinned[i] && self.intersects_at(i, rect))
+                    .map(|i| self.handles[i]),
+            );
+            return;
+        }
+
+        #[cfg(feature = "parallel")]
+        if span >= PARALLEL_MIN_CELLS {
+            let cells = self.compute_cells(min_x, min_y, max_x, max_y);
+            out.extend(self.query_rect_parallel(&cells, rect));
+            return;
+        }
+
+        let mut visited = self.visited.lock().unwrap_or_else(|e| e.into_inner());
+        visited.begin(self.handles.len());
+        let interior = self.interior_cells(rect);
+
+        for cell in self.cells_in(min_x, min_y, max_x, max_y) {
+            if let Some(slots) = self.grid.get(&cell) {
+                // Every node in a cell wholly inside the rect intersects it
+                let inside = interior(cell);
+                for &slot in slots {
+                    // AABB intersection test
+                    if visited.first_visit(slot as usize) && (inside || self.intersects_at(slot as usize, rect)) {
+                        out.push(self.handles[slot as usize]);
                     }
                 }
             }
         }
 
-        candidates
+        // Overflow nodes are not in the grid, test them directly
+        for &slot in &self.overflow {
+            if self.intersects_at(slot as usize, rect) {
+                out.push(self.handles[slot as usize]);
+            }
+        }
+    }
+
+    /// Get node counts for non-empty grid cells overlapping a rectangle, in row-major order
+    /// Counts reflect grid occupancy: nodes spanning several cells count once per cell,
+    /// overflow nodes are not part of any cell
+    pub fn cell_counts_in_rect(
+        &self,
+        min_x: f32,
+        min_y: f32,
+        max_x: f32,
+        max_y: f32,
+    ) -> Vec<(i32, i32, usize)> {
+        // For huge ranges walk the occupied cells instead of the whole range
+        if self.cell_span(min_x, min_y, max_x, max_y) > MAX_QUERY_CELLS {
+            let (min_cell_x, min_cell_y) = self.world_to_cell(min_x, min_y);
+            let (max_cell_x, max_cell_y) = self.world_to_cell(max_x, max_y);
+
+            let mut counts: Vec<(i32, i32, usize)> = self
+                .grid
+                .iter()
+                .filter(|(&(cx, cy), _)| {
+                    cx >= min_cell_x && cx <= max_cell_x && cy >= min_cell_y && cy <= max_cell_y
+                })
+                .map(|(&(cx, cy), slots)| (cx, cy, slots.len()))
+                .collect();
+            counts.sort_by_key(|&(cx, cy, _)| (cy, cx));
+            return counts;
+        }
+
+        self.compute_cells(min_x, min_y, max_x, max_y)
+            .into_iter()
+            .filter_map(|cell| {
+                self.grid
+                    .get(&cell)
+                    .map(|slots| (cell.0, cell.1, slots.len()))
+            })
+            .collect()
+    }
+
+    /// Union of all node bounds, or None when the index is empty
+    pub fn content_bounds(&self) -> Option<(f32, f32, f32, f32)> {
+        self.iter_bounds()
+            .map(|(_, b)| b)
+            .reduce(|r, b| (r.0.min(b.0), r.1.min(b.1), r.2.max(b.2), r.3.max(b.3)))
+    }
+
+    /// Bounds of the densest part of the scene, ignoring outliers
+    ///
+    /// Each node is counted once, in the grid cell holding its center. Cells are
+    /// taken densest first (ties by cell position) until they hold at least
+    /// `fraction` (0..=1) of all nodes; the result is the union of those nodes'
+    /// bounds. The chosen cells need not be adjacent, so two dense clusters
+    /// yield one box around both. None when the index is empty.
+    pub fn dense_region_bounds(&self, fraction: f32) -> Option<(f32, f32, f32, f32)> {
+        let mut by_cell: FxHashMap<(i32, i32), Vec<usize>> = FxHashMap::default();
+        for slot in (0..self.handles.len()).filter(|&i| self.live[i]) {
+            let (min_x, min_y, max_x, max_y) = self.bounds_at(slot);
+            let cell = self.world_to_cell((min_x + max_x) / 2.0, (min_y + max_y) / 2.0);
+            by_cell.entry(cell).or_default().push(slot);
+        }
+
+        let mut cells: Vec<((i32, i32), Vec<usize>)> = by_cell.into_iter().collect();
+        cells.sort_unstable_by(|a, b| b.1.len().cmp(&a.1.len()).then((a.0 .1, a.0 .0).cmp(&(b.0 .1, b.0 .0))));
+
+        let target = (self.len() as f32 * fraction.clamp(0.0, 1.0)).ceil().max(1.0) as usize;
+        let mut covered = 0;
+        let mut result: Option<(f32, f32, f32, f32)> = None;
+        for (_, slots) in cells {
+            if covered >= target {
+                break;
+            }
+            covered += slots.len();
+            for b in slots.into_iter().map(|slot| self.bounds_at(slot)) {
+                result = Some(match result {
+                    Some(r) => (r.0.min(b.0), r.1.min(b.1), r.2.max(b.2), r.3.max(b.3)),
+                    None => b,
+                });
+            }
+        }
+        result
     }
 
     /// Query nodes near a point (within radius)
@@ -148,43 +556,398 @@ impl SpatialIndex {
 
     /// Get bounds for a node
     pub fn get_bounds(&self, handle: u32) -> Option<(f32, f32, f32, f32)> {
-        self.nodes
+        self.slots
             .get(&handle)
-            .map(|n| (n.min_x, n.min_y, n.max_x, n.max_y))
+            .map(|&slot| self.bounds_at(slot as usize))
+    }
+
+    /// Get the z-index of a node
+    pub fn get_z_index(&self, handle: u32) -> Option<f32> {
+        self.slots.get(&handle).map(|&slot| self.z_index[slot as usize])
+    }
+
+    /// Iterate over all nodes and their bounds (order unspecified)
+    pub fn iter_bounds(&self) -> impl Iterator<Item = (u32, (f32, f32, f32, f32))> + '_ {
+        (0..self.handles.len())
+            .filter(|&i| self.live[i])
+            .map(|i| (self.handles[i], self.bounds_at(i)))
+    }
+
+    /// Get the number of grid cells a node spans (0 for unknown handles)
+    pub fn cell_count_for(&self, handle: u32) -> usize {
+        self.get_bounds(handle)
+            .map_or(0, |(min_x, min_y, max_x, max_y)| self.cell_span(min_x, min_y, max_x, max_y))
+    }
+
+    /// Check whether a node is kept in the overflow list instead of the grid
+    pub fn is_overflow(&self, handle: u32) -> bool {
+        self.slots
+            .get(&handle)
+            .is_some_and(|&slot| self.in_overflow[slot as usize])
+    }
+
+    /// Check whether a node is pinned (kept out of the grid and area queries)
+    pub fn is_pinned(&self, handle: u32) -> bool {
+        self.slots
+            .get(&handle)
+            .is_some_and(|&slot| self.in_pinned[slot as usize])
+    }
+
+    /// Iterate over pinned node handles (order unspecified)
+    pub fn pinned_handles(&self) -> impl Iterator<Item = u32> + '_ {
+        self.pinned.iter().map(|&slot| self.handles[slot as usize])
     }
 
     /// Get node count
     pub fn len(&self) -> usize {
-        self.nodes.len()
+        self.slots.len()
+    }
+
+    /// Check if the index holds no nodes
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// Reclaim memory after large deletions
+    ///
+    /// Compacts the node arrays when most slots are free, drops empty cells,
+    /// shrinks oversized cell vectors and, when occupancy falls below
+    /// `GC_SHRINK_RATIO`, shrinks the grid map itself. Call it occasionally
+    /// (e.g. after bulk deletes or on idle), not every frame: compaction and
+    /// shrinking rebuild the whole grid.
+    pub fn gc(&mut self) {
+        if self.free_slots.len() * 2 > self.handles.len() {
+            self.rebuild();
+            self.shrink_arrays();
+        }
+
+        self.grid.retain(|_, slots| !slots.is_empty());
+        for slots in self.grid.values_mut() {
+            if slots.capacity() > slots.len() * 2 {
+                slots.shrink_to_fit();
+            }
+        }
+
+        let occupancy = self.grid.len() as f32 / self.grid.capacity().max(1) as f32;
+        if occupancy < GC_SHRINK_RATIO {
+            self.grid.shrink_to((self.grid.len() * 2).max(MIN_GRID_CAPACITY));
+        }
+        if self.slots.len() * 4 < self.slots.capacity() {
+            self.slots.shrink_to(self.slots.len() * 2);
+        }
+        self.overflow.shrink_to_fit();
+        self.pinned.shrink_to_fit();
+    }
+
+    /// Estimate the heap memory held by the index, in bytes
+    /// Counts allocated capacity of the maps, per-slot arrays and cell vectors;
+    /// allocator overhead is ignored, so treat it as a rough figure.
+    pub fn estimated_memory_bytes(&self) -> usize {
+        let slot_arrays = vec_bytes::<u32>(self.handles.capacity())
+            + vec_bytes::<f32>(self.min_x.capacity())
+            + vec_bytes::<f32>(self.min_y.capacity())
+            + vec_bytes::<f32>(self.max_x.capacity())
+            + vec_bytes::<f32>(self.max_y.capacity())
+            + vec_bytes::<f32>(self.z_index.capacity())
+            + vec_bytes::<bool>(self.in_overflow.capacity())
+            + vec_bytes::<bool>(self.in_pinned.capacity())
+            + vec_bytes::<bool>(self.live.capacity())
+            + vec_bytes::<u32>(self.free_slots.capacity());
+
+        let cells: usize = self.grid.values().map(|slots| vec_bytes::<u32>(slots.capacity())).sum();
+        let sub_grids: usize = self
+            .sub_grids
+            .values()
+            .map(|sub_grid| {
+                vec_bytes::<Vec<u32>>(sub_grid.buckets.capacity())
+                    + sub_grid.buckets.iter().map(|b| vec_bytes::<u32>(b.capacity())).sum::<usize>()
+            })
+            .sum();
+
+        let stamps = self.visited.lock().map_or(0, |v| vec_bytes::<u32>(v.stamps.capacity()));
+
+        map_bytes::<u32, u32>(self.slots.capacity())
+            + slot_arrays
+            + map_bytes::<(i32, i32), Vec<u32>>(self.grid.capacity())
+            + cells
+            + map_bytes::<(i32, i32), SubGrid>(self.sub_grids.capacity())
+            + sub_grids
+            + vec_bytes::<u32>(self.overflow.capacity())
+            + vec_bytes::<u32>(self.pinned.capacity())
+            + stamps
+    }
+
+    /// Reserve room for `additional` more nodes
+    pub fn reserve(&mut self, additional: usize) {
+        self.slots.reserve(additional);
+        self.handles.reserve(additional);
+        self.min_x.reserve(additional);
+        self.min_y.reserve(additional);
+        self.max_x.reserve(additional);
+        self.max_y.reserve(additional);
+        self.z_index.reserve(additional);
+        self.in_overflow.reserve(additional);
+        self.in_pinned.reserve(additional);
+        self.live.reserve(additional);
+        self.grid.reserve(additional.saturating_mul(4));
     }
 
     /// Clear all nodes
     pub fn clear(&mut self) {
-        self.nodes.clear();
+        self.slots.clear();
+        self.handles.clear();
+        self.min_x.clear();
+        self.min_y.clear();
+        self.max_x.clear();
+        self.max_y.clear();
+        self.z_index.clear();
+        self.in_overflow.clear();
+        self.in_pinned.clear();
+        self.live.clear();
+        self.free_slots.clear();
         self.grid.clear();
+        self.sub_grids.clear();
+        self.overflow.clear();
+        self.pinned.clear();
+    }
+
+    /// Get the grid cell containing a world point (floored, so negatives round down)
+    pub fn world_to_cell(&self, x: f32, y: f32) -> (i32, i32) {
+        let cell_x = (x / self.cell_width).floor() as i32;
+        let cell_y = (y / self.cell_height).floor() as i32;
+        (cell_x, cell_y)
     }
 
     // ========================================================================
     // Internal Helpers
     // ========================================================================
 
-    fn world_to_cell(&self, x: f32, y: f32) -> (i32, i32) {
-        let cell_x = (x / GRID_CELL_SIZE).floor() as i32;
-        let cell_y = (y / GRID_CELL_SIZE).floor() as i32;
-        (cell_x, cell_y)
+    /// Order two z-indices in the configured direction (NaN sorts as lowest)
+    fn compare_z(&self, a: f32, b: f32) -> Ordering {
+        let key = |z: f32| if z.is_nan() { f32::NEG_INFINITY } else { z };
+        let ordering = key(a).partial_cmp(&key(b)).unwrap_or(Ordering::Equal);
+        if self.z_descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+
+    /// Bounds stored in a slot
+    fn bounds_at(&self, i: usize) -> (f32, f32, f32, f32) {
+        (self.min_x[i], self.min_y[i], self.max_x[i], self.max_y[i])
+    }
+
+    /// Inclusive AABB test between a slot and a rect
+    fn intersects_at(&self, i: usize, rect: (f32, f32, f32, f32)) -> bool {
+        !(self.max_x[i] < rect.0 || self.min_x[i] > rect.2 || self.max_y[i] < rect.1 || self.min_y[i] > rect.3)
+    }
+
+    /// Multi-threaded `query_rect` over a precomputed cell list
+    ///
+    /// Threads can't share visit marks, so a node is reported only by the cell
+    /// holding the top-left corner of its overlap with the query rect: that
+    /// cell is unique and always part of both the node's and the query's cells.
+    #[cfg(feature = "parallel")]
+    fn query_rect_parallel(&self, cells: &[(i32, i32)], rect: (f32, f32, f32, f32)) -> Vec<u32> {
+        let interior = self.interior_cells(rect);
+        let mut candidates: Vec<u32> = cells
+            .par_iter()
+            .flat_map_iter(|cell| {
+                let slots = self.grid.get(cell).map(|slots| slots.as_slice()).unwrap_or(&[]);
+                let inside = interior(*cell);
+                slots.iter().filter_map(move |&slot| {
+                    let i = slot as usize;
+                    let owner = self.world_to_cell(self.min_x[i].max(rect.0), self.min_y[i].max(rect.1));
+                    (owner == *cell && (inside || self.intersects_at(i, rect))).then(|| self.handles[i])
+                })
+            })
+            .collect();
+
+        // Overflow nodes are not in the grid, test them directly
+        for &slot in &self.overflow {
+            if self.intersects_at(slot as usize, rect) {
+                candidates.push(self.handles[slot as usize]);
+            }
+        }
+
+        candidates
+    }
+
+    /// Predicate for cells lying wholly inside a rect
+    ///
+    /// A node is only stored in cells its bounds overlap, so any node found in
+    /// such a cell intersects the rect (even if it extends past the rect's edge)
+    /// and needs no narrow-phase test.
+    fn interior_cells(&self, rect: (f32, f32, f32, f32)) -> impl Fn((i32, i32)) -> bool + Sync {
+        let min_cx = (rect.0 / self.cell_width).ceil() as i32;
+        let min_cy = (rect.1 / self.cell_height).ceil() as i32;
+        let max_cx = (rect.2 / self.cell_width).floor() as i32 - 1;
+        let max_cy = (rect.3 / self.cell_height).floor() as i32 - 1;
+
+        move |(cx, cy)| cx >= min_cx && cx <= max_cx && cy >= min_cy && cy <= max_cy
+    }
+
+    /// Re-insert every live node into fresh, densely packed slots
+    fn rebuild(&mut self) {
+        // (handle, [min_x, min_y, max_x, max_y, z_index], pinned)
+        let nodes: Vec<(u32, [f32; 5], bool)> = (0..self.handles.len())
+            .filter(|&i| self.live[i])
+            .map(|i| {
+                let (min_x, min_y, max_x, max_y) = self.bounds_at(i);
+                (self.handles[i], [min_x, min_y, max_x, max_y, self.z_index[i]], self.in_pinned[i])
+            })
+            .collect();
+        self.clear();
+
+        for (handle, [min_x, min_y, max_x, max_y, z_index], pinned) in nodes {
+            self.upsert_placed(handle, (min_x, min_y, max_x, max_y), z_index, pinned);
+        }
+    }
+
+    /// Release spare capacity of the per-slot arrays
+    fn shrink_arrays(&mut self) {
+        self.handles.shrink_to_fit();
+        self.min_x.shrink_to_fit();
+        self.min_y.shrink_to_fit();
+        self.max_x.shrink_to_fit();
+        self.max_y.shrink_to_fit();
+        self.z_index.shrink_to_fit();
+        self.in_overflow.shrink_to_fit();
+        self.in_pinned.shrink_to_fit();
+        self.live.shrink_to_fit();
+        self.free_slots.shrink_to_fit();
+
+        let visited = self.visited.get_mut().unwrap_or_else(|e| e.into_inner());
+        visited.stamps.truncate(self.handles.len());
+        visited.stamps.shrink_to_fit();
+    }
+
+    /// Move a grid node's cell membership from its old bounds to new bounds,
+    /// touching only the cells that are entered or left
+    fn move_cells(&mut self, slot: u32, old: (f32, f32, f32, f32), new: (f32, f32, f32, f32)) {
+        let (old_min_cx, old_min_cy) = self.world_to_cell(old.0, old.1);
+        let (old_max_cx, old_max_cy) = self.world_to_cell(old.2, old.3);
+        let (new_min_cx, new_min_cy) = self.world_to_cell(new.0, new.1);
+        let (new_max_cx, new_max_cy) = self.world_to_cell(new.2, new.3);
+
+        // Same cells and none of them has sub-grid buckets to update: nothing to do
+        let same_cells = (old_min_cx, old_min_cy, old_max_cx, old_max_cy)
+            == (new_min_cx, new_min_cy, new_max_cx, new_max_cy);
+        if same_cells
+            && (self.sub_grids.is_empty()
+                || !self.cells_in(old.0, old.1, old.2, old.3).any(|cell| self.sub_grids.contains_key(&cell)))
+        {
+            return;
+        }
+
+        let in_old = |(cx, cy): (i32, i32)| {
+            cx >= old_min_cx && cx <= old_max_cx && cy >= old_min_cy && cy <= old_max_cy
+        };
+        let in_new = |(cx, cy): (i32, i32)| {
+            cx >= new_min_cx && cx <= new_max_cx && cy >= new_min_cy && cy <= new_max_cy
+        };
+
+        // Leave cells no longer covered
+        for cell in self.compute_cells(old.0, old.1, old.2, old.3) {
+            if !in_new(cell) {
+                self.cell_remove(cell, slot, old);
+            } else if self.sub_grids.contains_key(&cell) {
+                // Still covered, but may have changed buckets within the cell
+                let old_range = self.bucket_range(cell, old);
+                let new_range = self.bucket_range(cell, new);
+                if let Some(sub_grid) = self.sub_grids.get_mut(&cell) {
+                    sub_grid.remove(slot, old_range);
+                    sub_grid.insert(slot, new_range);
+                }
+            }
+        }
+
+        // Enter newly covered cells
+        for cell in self.compute_cells(new.0, new.1, new.2, new.3) {
+            if !in_old(cell) {
+                self.cell_insert(cell, slot, new);
+            }
+        }
+    }
+
+    /// Add a slot with the given bounds to a grid cell, building the cell's
+    /// sub-grid once it gets crowded
+    fn cell_insert(&mut self, cell: (i32, i32), slot: u32, bounds: (f32, f32, f32, f32)) {
+        let slots = self.grid.entry(cell).or_default();
+        slots.push(slot);
+        let len = slots.len();
+
+        let range = self.bucket_range(cell, bounds);
+        if let Some(sub_grid) = self.sub_grids.get_mut(&cell) {
+            sub_grid.insert(slot, range);
+        } else if len > SUBGRID_THRESHOLD {
+            let mut sub_grid = SubGrid::new();
+            for &s in &self.grid[&cell] {
+                let s_bounds = if s == slot { bounds } else { self.bounds_at(s as usize) };
+                sub_grid.insert(s, self.bucket_range(cell, s_bounds));
+            }
+            self.sub_grids.insert(cell, sub_grid);
+        }
+    }
+
+    /// Remove a slot, inserted with the given bounds, from a grid cell, dropping the
+    /// cell's sub-grid once it falls to half the threshold (the gap avoids
+    /// rebuilding on every edit)
+    fn cell_remove(&mut self, cell: (i32, i32), slot: u32, bounds: (f32, f32, f32, f32)) {
+        let Some(slots) = self.grid.get_mut(&cell) else {
+            return;
+        };
+        slots.retain(|&s| s != slot);
+        let len = slots.len();
+        if len == 0 {
+            self.grid.remove(&cell);
+        }
+
+        let range = self.bucket_range(cell, bounds);
+        if len <= SUBGRID_THRESHOLD / 2 {
+            self.sub_grids.remove(&cell);
+        } else if let Some(sub_grid) = self.sub_grids.get_mut(&cell) {
+            sub_grid.remove(slot, range);
+        }
+    }
+
+    /// Sub-grid buckets of `cell` covered by a rect, clamped to the cell
+    fn bucket_range(&self, cell: (i32, i32), rect: (f32, f32, f32, f32)) -> BucketRange {
+        let bucket_width = self.cell_width / SUBGRID_DIVISIONS as f32;
+        let bucket_height = self.cell_height / SUBGRID_DIVISIONS as f32;
+        let origin_x = cell.0 as f32 * self.cell_width;
+        let origin_y = cell.1 as f32 * self.cell_height;
+
+        let last = (SUBGRID_DIVISIONS - 1) as f32;
+        let bucket = |v: f32, origin: f32, size: f32| ((v - origin) / size).floor().clamp(0.0, last) as usize;
+        (
+            bucket(rect.0, origin_x, bucket_width),
+            bucket(rect.1, origin_y, bucket_height),
+            bucket(rect.2, origin_x, bucket_width),
+            bucket(rect.3, origin_y, bucket_height),
+        )
+    }
+
+    /// Number of cells covered by a rect, without enumerating them
+    fn cell_span(&self, min_x: f32, min_y: f32, max_x: f32, max_y: f32) -> usize {
+        let (min_cell_x, min_cell_y) = self.world_to_cell(min_x, min_y);
+        let (max_cell_x, max_cell_y) = self.world_to_cell(max_x, max_y);
+        let span_x = (max_cell_x as i64 - min_cell_x as i64 + 1).max(0) as usize;
+        let span_y = (max_cell_y as i64 - min_cell_y as i64 + 1).max(0) as usize;
+        span_x.saturating_mul(span_y)
     }
 
     fn compute_cells(&self, min_x: f32, min_y: f32, max_x: f32, max_y: f32) -> Vec<(i32, i32)> {
+        self.cells_in(min_x, min_y, max_x, max_y).collect()
+    }
+
+    /// Cells covered by a rect in row-major order, without allocating
+    fn cells_in(&self, min_x: f32, min_y: f32, max_x: f32, max_y: f32) -> impl Iterator<Item = (i32, i32)> {
         let (min_cell_x, min_cell_y) = self.world_to_cell(min_x, min_y);
         let (max_cell_x, max_cell_y) = self.world_to_cell(max_x, max_y);
 
-        let mut cells = Vec::new();
-        for cy in min_cell_y..=max_cell_y {
-            for cx in min_cell_x..=max_cell_x {
-                cells.push((cx, cy));
-            }
-        }
-        cells
+        (min_cell_y..=max_cell_y).flat_map(move |cy| (min_cell_x..=max_cell_x).map(move |cx| (cx, cy)))
     }
 }
 
@@ -192,12 +955,17 @@ impl SpatialIndex {
 mod tests {
     use super::*;
 
+    /// Handles stored in a grid cell
+    fn cell_handles(index: &SpatialIndex, cell: (i32, i32)) -> Vec<u32> {
+        index.grid[&cell].iter().map(|&slot| index.handles[slot as usize]).collect()
+    }
+
     #[test]
     fn test_insert_and_query() {
         let mut index = SpatialIndex::new(100);
         
         // Insert a node
-        index.upsert(1, 0.0, 0.0, 100.0, 100.0, 0);
+        index.upsert(1, 0.0, 0.0, 100.0, 100.0, 0.0);
         
         // Query point inside
         let hits = index.query_point(50.0, 50.0);
@@ -209,11 +977,41 @@ mod tests {
         assert_eq!(hits.len(), 0);
     }
 
+    #[test]
+    fn test_zero_capacity() {
+        let mut index = SpatialIndex::new(0);
+        assert!(index.grid.capacity() >= MIN_GRID_CAPACITY);
+
+        index.upsert(1, 0.0, 0.0, 100.0, 100.0, 0.0);
+        assert_eq!(index.len(), 1);
+        assert_eq!(index.query_point(50.0, 50.0), vec![1]);
+    }
+
+    #[test]
+    fn test_world_to_cell_negative() {
+        let index = SpatialIndex::new(100);
+        assert_eq!(index.world_to_cell(0.0, 255.9), (0, 0));
+        assert_eq!(index.world_to_cell(-0.1, -256.0), (-1, -1));
+        assert_eq!(index.world_to_cell(-256.1, 512.0), (-2, 2));
+    }
+
+    #[test]
+    fn test_query_point_outside_origin_cell() {
+        let mut index = SpatialIndex::new(100);
+
+        // Origin cell (0, 0); spans up to cell (2, 1)
+        index.upsert(1, 10.0, 10.0, 700.0, 400.0, 0.0);
+
+        assert_eq!(index.query_point(650.0, 350.0), vec![1]);
+        assert_eq!(index.query_point(300.0, 300.0), vec![1]);
+        assert!(index.query_point(710.0, 350.0).is_empty());
+    }
+
     #[test]
     fn test_remove() {
         let mut index = SpatialIndex::new(100);
         
-        index.upsert(1, 0.0, 0.0, 100.0, 100.0, 0);
+        index.upsert(1, 0.0, 0.0, 100.0, 100.0, 0.0);
         assert_eq!(index.len(), 1);
         
         index.remove(1);
@@ -228,12 +1026,418 @@ mod tests {
         let mut index = SpatialIndex::new(100);
         
         // Insert overlapping nodes with different z-indices
-        index.upsert(1, 0.0, 0.0, 100.0, 100.0, 1);
-        index.upsert(2, 0.0, 0.0, 100.0, 100.0, 5);
-        index.upsert(3, 0.0, 0.0, 100.0, 100.0, 3);
+        index.upsert(1, 0.0, 0.0, 100.0, 100.0, 1.0);
+        index.upsert(2, 0.0, 0.0, 100.0, 100.0, 5.0);
+        index.upsert(3, 0.0, 0.0, 100.0, 100.0, 3.0);
         
         // Query should return in z-index order (highest first)
         let hits = index.query_point(50.0, 50.0);
         assert_eq!(hits, vec![2, 3, 1]);
+
+        // Bottommost-first when configured
+        index.set_z_order_descending(false);
+        assert_eq!(index.query_point(50.0, 50.0), vec![1, 3, 2]);
+    }
+
+    #[test]
+    fn test_fractional_z_index() {
+        let mut index = SpatialIndex::new(100);
+        index.upsert(1, 0.0, 0.0, 100.0, 100.0, 3.0);
+        index.upsert(2, 0.0, 0.0, 100.0, 100.0, 4.0);
+
+        // Reorder between 3 and 4 without renumbering
+        index.upsert(3, 0.0, 0.0, 100.0, 100.0, 3.5);
+        index.upsert(4, 0.0, 0.0, 100.0, 100.0, f32::NAN);
+        assert_eq!(index.query_point(50.0, 50.0), vec![2, 3, 1, 4]);
+
+        index.set_z_order_descending(false);
+        assert_eq!(index.query_point(50.0, 50.0), vec![4, 1, 3, 2]);
+    }
+
+    #[test]
+    fn test_cell_count_for() {
+        let mut index = SpatialIndex::new(100);
+
+        index.upsert(1, 10.0, 10.0, 20.0, 20.0, 0.0);
+        index.upsert(2, -10.0, 0.0, 600.0, 300.0, 0.0);
+
+        assert_eq!(index.cell_count_for(1), 1);
+        // Spans cells -1..=2 on x and 0..=1 on y
+        assert_eq!(index.cell_count_for(2), 8);
+        assert_eq!(index.cell_count_for(99), 0);
+    }
+
+    #[test]
+    fn test_translate() {
+        let mut index = SpatialIndex::new(100);
+        index.upsert(1, 0.0, 0.0, 10.0, 10.0, 7.0);
+
+        assert!(index.translate(1, 500.0, -20.0));
+        assert_eq!(index.get_bounds(1), Some((500.0, -20.0, 510.0, -10.0)));
+        assert!(index.query_point(5.0, 5.0).is_empty());
+        assert_eq!(index.query_point(505.0, -15.0), vec![1]);
+        assert_eq!(index.z_index[index.slots[&1] as usize], 7.0);
+
+        assert!(!index.translate(99, 1.0, 1.0));
+    }
+
+    #[test]
+    fn test_translate_moves_only_changed_cells() {
+        let mut index = SpatialIndex::new(100);
+        index.upsert(1, 0.0, 0.0, 10.0, 10.0, 0.0);
+        // Spans cells (0, 0) and (1, 0)
+        index.upsert(2, 250.0, 0.0, 260.0, 10.0, 0.0);
+
+        assert!(index.translate(1, 10.0, 0.0));
+        assert!(index.translate(2, 10.0, 0.0));
+
+        // Node 1 stays in its cell, node 2 leaves cell (0, 0)
+        assert_eq!(cell_handles(&index, (0, 0)), vec![1]);
+        assert_eq!(cell_handles(&index, (1, 0)), vec![2]);
+        assert_eq!(index.query_point(265.0, 5.0), vec![2]);
+        assert_eq!(index.query_rect(0.0, 0.0, 511.0, 511.0).len(), 2);
+    }
+
+    #[test]
+    fn test_query_rect_dedupes_multi_cell_node() {
+        let mut index = SpatialIndex::new(10);
+        // Spans 8x8 cells
+        index.upsert(1, 0.0, 0.0, 2000.0, 2000.0, 0.0);
+        index.upsert(2, 100.0, 100.0, 110.0, 110.0, 0.0);
+
+        for _ in 0..3 {
+            let mut hits = index.query_rect(0.0, 0.0, 2048.0, 2048.0);
+            hits.sort();
+            assert_eq!(hits, vec![1, 2]);
+        }
+
+        // Wrapping the query counter resets the marks
+        index.visited.get_mut().unwrap().query_id = u32::MAX;
+        assert_eq!(index.query_rect(0.0, 0.0, 2048.0, 2048.0).len(), 2);
+        assert_eq!(index.query_rect(0.0, 0.0, 2048.0, 2048.0).len(), 2);
+    }
+
+    #[test]
+    fn test_query_rect_many_cells_matches_scan() {
+        // Enough cells to take the parallel path when the feature is enabled
+        let mut index = SpatialIndex::new(1000);
+        for handle in 0..1000u32 {
+            let x = (handle % 40) as f32 * 150.0;
+            let y = (handle / 40) as f32 * 150.0;
+            index.upsert(handle, x, y, x + 400.0, y + 300.0, 0.0);
+        }
+
+        let rect = (500.0, 200.0, 5200.0, 3300.0);
+        let mut hits = index.query_rect(rect.0, rect.1, rect.2, rect.3);
+        hits.sort();
+
+        let expected: Vec<u32> = (0..1000u32)
+            .filter(|&h| index.intersects_at(index.slots[&h] as usize, rect))
+            .collect();
+        assert_eq!(hits, expected);
+    }
+
+    #[test]
+    fn test_anisotropic_cells() {
+        let mut index = SpatialIndex::new(10);
+        // Timeline-like tracks: wide and short
+        for track in 0..4u32 {
+            let y = track as f32 * 40.0;
+            index.upsert(track, 0.0, y, 2000.0, y + 30.0, 0.0);
+        }
+        let entries = |index: &SpatialIndex| index.grid.values().map(Vec::len).sum::<usize>();
+        assert_eq!(entries(&index), 32);
+
+        index.set_cell_size(4096.0, 64.0);
+        assert_eq!(entries(&index), 6); // tracks 1 and 3 straddle a row boundary
+        assert_eq!(index.world_to_cell(5000.0, 100.0), (1, 1));
+        assert_eq!(index.query_rect(1500.0, 45.0, 1600.0, 50.0), vec![1]);
+        assert_eq!(index.query_point(10.0, 125.0), vec![3]);
+    }
+
+    #[test]
+    fn test_query_rect_interior_cells() {
+        let mut index = SpatialIndex::new(10);
+        // Rect covers cells 1..=2 fully on both axes, partially cells 0 and 3
+        let rect = (200.0, 200.0, 800.0, 800.0);
+
+        // Interior cell node straddling the far edge of the rect
+        index.upsert(1, 700.0, 300.0, 900.0, 350.0, 0.0);
+        // Border cell (0, 0): inside the rect, and just outside it
+        index.upsert(2, 210.0, 210.0, 220.0, 220.0, 0.0);
+        index.upsert(3, 100.0, 100.0, 199.0, 199.0, 0.0);
+        // Border cell (3, 3): straddling the rect edge, and just outside it
+        index.upsert(4, 790.0, 790.0, 810.0, 810.0, 0.0);
+        index.upsert(5, 801.0, 801.0, 810.0, 810.0, 0.0);
+
+        let mut hits = index.query_rect(rect.0, rect.1, rect.2, rect.3);
+        hits.sort();
+        assert_eq!(hits, vec![1, 2, 4]);
+    }
+
+    #[test]
+    fn test_slots_recycled_and_compacted() {
+        let mut index = SpatialIndex::new(10);
+        for handle in 0..8u32 {
+            let x = handle as f32 * 300.0;
+            index.upsert(handle, x, 0.0, x + 10.0, 10.0, 0.0);
+        }
+
+        // A removed slot is reused by the next insert
+        let slot = index.slots[&3];
+        index.remove(3);
+        index.upsert(42, 5.0, 5.0, 15.0, 15.0, 0.0);
+        assert_eq!(index.slots[&42], slot);
+        assert_eq!(index.handles.len(), 8);
+
+        // Mostly-free arrays are compacted by gc
+        for handle in 0..6u32 {
+            index.remove(handle);
+        }
+        index.gc();
+        assert_eq!(index.handles.len(), 3);
+        assert!(index.free_slots.is_empty());
+
+        let mut hits = index.query_rect(0.0, 0.0, 10_000.0, 20.0);
+        hits.sort();
+        assert_eq!(hits, vec![6, 7, 42]);
+    }
+
+    #[test]
+    fn test_gc_shrinks_grid() {
+        let mut index = SpatialIndex::new(0);
+
+        // Scatter nodes over many distinct cells, then delete most of them
+        for i in 0..4096u32 {
+            let x = (i % 64) as f32 * 1000.0;
+            let y = (i / 64) as f32 * 1000.0;
+            index.upsert(i, x, y, x + 10.0, y + 10.0, 0.0);
+        }
+        let peak_capacity = index.grid.capacity();
+        for i in 10..4096u32 {
+            index.remove(i);
+        }
+
+        index.gc();
+        assert_eq!(index.grid.len(), 10);
+        assert!(index.grid.capacity() < peak_capacity / 4);
+        assert_eq!(index.query_rect(0.0, 0.0, 10_000.0, 10.0).len(), 10);
+    }
+
+    #[test]
+    fn test_huge_query_rect_scans_nodes() {
+        let mut index = SpatialIndex::new(100);
+        index.upsert(1, 0.0, 0.0, 10.0, 10.0, 0.0);
+        index.upsert(2, 1.0e6, -1.0e6, 1.0e6 + 10.0, -1.0e6 + 10.0, 0.0);
+
+        let mut hits = index.query_rect(-1.0e9, -1.0e9, 1.0e9, 1.0e9);
+        hits.sort();
+        assert_eq!(hits, vec![1, 2]);
+
+        let counts = index.cell_counts_in_rect(-1.0e9, -1.0e9, 1.0e9, 1.0e9);
+        assert_eq!(counts, vec![(3906, -3907, 1), (0, 0, 1)]);
+    }
+
+    #[test]
+    fn test_dense_region_bounds() {
+        let mut index = SpatialIndex::new(16);
+        assert_eq!(index.dense_region_bounds(0.9), None);
+
+        // Cluster of 4 in cell (0, 0), 2 in cell (1, 0), one stray far away
+        for (i, x) in [10.0, 40.0, 70.0, 100.0].into_iter().enumerate() {
+            index.upsert(i as u32, x, 10.0, x + 20.0, 30.0, 0.0);
+        }
+        index.upsert(10, 300.0, 10.0, 320.0, 30.0, 0.0);
+        index.upsert(11, 330.0, 50.0, 350.0, 70.0, 0.0);
+        index.upsert(20, 1.0e5, 1.0e5, 1.0e5 + 10.0, 1.0e5 + 10.0, 0.0);
+
+        assert_eq!(index.dense_region_bounds(0.5), Some((10.0, 10.0, 120.0, 30.0)));
+        assert_eq!(index.dense_region_bounds(0.8), Some((10.0, 10.0, 350.0, 70.0)));
+        assert_eq!(index.dense_region_bounds(1.0).map(|b| b.2), Some(1.0e5 + 10.0));
+    }
+
+    #[test]
+    fn test_cell_counts_in_rect() {
+        let mut index = SpatialIndex::new(100);
+
+        index.upsert(1, 10.0, 10.0, 20.0, 20.0, 0.0);
+        index.upsert(2, 30.0, 30.0, 40.0, 40.0, 0.0);
+        // Spans cells (0, 0) and (1, 0)
+        index.upsert(3, 200.0, 10.0, 300.0, 20.0, 0.0);
+
+        let counts = index.cell_counts_in_rect(0.0, 0.0, 511.0, 511.0);
+        assert_eq!(counts, vec![(0, 0, 3), (1, 0, 1)]);
+    }
+
+    #[test]
+    fn test_overflow_node_still_queried() {
+        let mut index = SpatialIndex::new(100);
+        index.set_overflow_cell_threshold(16);
+
+        // Canvas-spanning background plus a small node on top
+        index.upsert(1, -10_000.0, -10_000.0, 10_000.0, 10_000.0, 0.0);
+        index.upsert(2, 0.0, 0.0, 10.0, 10.0, 1.0);
+
+        assert!(index.is_overflow(1));
+        assert!(!index.is_overflow(2));
+        assert_eq!(index.grid.len(), 1);
+
+        assert_eq!(index.query_point(5.0, 5.0), vec![2, 1]);
+        assert_eq!(index.query_point(-9_000.0, 9_000.0), vec![1]);
+        assert_eq!(index.query_rect(5_000.0, 5_000.0, 5_100.0, 5_100.0), vec![1]);
+
+        index.remove(1);
+        assert!(index.query_point(-9_000.0, 9_000.0).is_empty());
+        assert!(index.overflow.is_empty());
+    }
+
+    #[test]
+    fn test_pinned_node_skips_grid() {
+        let mut index = SpatialIndex::new(10);
+        index.upsert_placed(1, (-1e6, -1e6, 1e6, 1e6), 0.0, true);
+        index.upsert(2, 0.0, 0.0, 10.0, 10.0, 1.0);
+
+        assert!(index.is_pinned(1));
+        assert!(index.grid.values().all(|slots| slots.len() == 1));
+        assert!(index.overflow.is_empty());
+        assert_eq!(index.query_rect(-5.0, -5.0, 5.0, 5.0), vec![2]);
+        assert_eq!(index.query_rect(-1e5, -1e5, 1e5, 1e5), vec![2]); // linear-scan path
+        assert_eq!(index.query_point(5.0, 5.0), vec![2, 1]);
+        assert_eq!(index.get_bounds(1), Some((-1e6, -1e6, 1e6, 1e6)));
+
+        // Updates and re-indexing keep it pinned; unpinning puts it back in the grid
+        index.upsert(1, -10.0, -10.0, 10.0, 10.0, 0.0);
+        index.set_cell_size(64.0, 64.0);
+        assert!(index.is_pinned(1));
+        assert!(index.set_pinned(1, false));
+        assert_eq!(index.query_rect(-5.0, -5.0, 5.0, 5.0).len(), 2);
+
+        index.set_pinned(1, true);
+        index.remove(1);
+        assert_eq!(index.pinned_handles().count(), 0);
+        assert!(!index.set_pinned(1, true));
+    }
+
+    #[test]
+    fn test_overflow_threshold_reindexes() {
+        let mut index = SpatialIndex::new(100);
+        index.upsert(1, 0.0, 0.0, 1000.0, 1000.0, 0.0);
+        assert!(!index.is_overflow(1));
+
+        index.set_overflow_cell_threshold(4);
+        assert!(index.is_overflow(1));
+        assert!(index.grid.is_empty());
+        assert_eq!(index.query_point(900.0, 900.0), vec![1]);
+    }
+
+    #[test]
+    fn test_overflow_threshold_capped() {
+        let mut index = SpatialIndex::new(10);
+        index.set_overflow_cell_threshold(usize::MAX);
+        assert_eq!(index.overflow_cell_threshold, MAX_NODE_CELLS);
+
+        // 1000 x 1000 cells: kept out of the grid instead of enumerating a million cells
+        index.upsert(1, 0.0, 0.0, 256_000.0 - 1.0, 256_000.0 - 1.0, 0.0);
+        assert!(index.is_overflow(1));
+        assert!(index.grid.is_empty());
+
+        // Non-finite bounds saturate the span rather than enumerating cells
+        index.upsert(2, f32::NEG_INFINITY, 0.0, f32::INFINITY, 10.0, 0.0);
+        assert!(index.is_overflow(2));
+        assert!(index.grid.is_empty());
+
+        let mut hits = index.query_point(100_000.0, 5.0);
+        hits.sort();
+        assert_eq!(hits, vec![1, 2]);
+        index.remove(1);
+        index.remove(2);
+        assert!(index.is_empty());
+    }
+
+    #[test]
+    fn test_dense_cell_sub_grid() {
+        let mut index = SpatialIndex::new(1000);
+        // 20x20 dots of 5x5 on a 10px pitch inside cell (0, 0)
+        for handle in 0..400u32 {
+            let x = (handle % 20) as f32 * 10.0;
+            let y = (handle / 20) as f32 * 10.0;
+            index.upsert(handle, x, y, x + 5.0, y + 5.0, handle as f32);
+        }
+        assert!(index.sub_grids.contains_key(&(0, 0)));
+
+        // Move some dots around within the cell and drop others
+        for handle in 0..50 {
+            index.translate(handle, 3.0, 7.0);
+        }
+        // A node in an uncrowded cell moves within it (the no-op fast path)
+        index.upsert(1000, 1000.0, 1000.0, 1010.0, 1010.0, 0.0);
+        index.translate(1000, 20.0, 20.0);
+        assert_eq!(index.query_point(1025.0, 1025.0), vec![1000]);
+        assert!(index.query_point(1005.0, 1005.0).is_empty());
+        index.remove(1000);
+        for handle in 300..400 {
+            index.remove(handle);
+        }
+
+        let brute_force = |index: &SpatialIndex, x: f32, y: f32| -> Vec<u32> {
+            let mut hits: Vec<(u32, f32)> = index
+                .iter_bounds()
+                .filter(|&(_, b)| x >= b.0 && x <= b.2 && y >= b.1 && y <= b.3)
+                .map(|(h, _)| (h, index.get_z_index(h).unwrap()))
+                .collect();
+            hits.sort_by(|a, b| b.1.total_cmp(&a.1));
+            hits.into_iter().map(|(h, _)| h).collect()
+        };
+        for i in 0..100 {
+            let (x, y) = (i as f32 * 2.3, i as f32 * 1.7 + 0.4);
+            assert_eq!(index.query_point(x, y), brute_force(&index, x, y), "at ({x}, {y})");
+        }
+
+        // Dropping to half the threshold removes the sub-grid
+        for handle in 0..300 - SUBGRID_THRESHOLD as u32 / 2 {
+            index.remove(handle);
+        }
+        assert!(!index.sub_grids.contains_key(&(0, 0)));
+        assert_eq!(index.query_point(195.0, 145.0), vec![299]);
+    }
+
+    #[test]
+    fn test_upsert_in_place() {
+        let mut index = SpatialIndex::new(10);
+        index.upsert(1, 10.0, 10.0, 20.0, 20.0, 0.0);
+        index.upsert(2, 0.0, 0.0, 5.0, 5.0, 0.0);
+        let slot = index.slots[&1];
+
+        // Sub-cell move keeps the slot and the cell lists untouched
+        index.upsert(1, 11.0, 10.0, 21.0, 20.0, 3.0);
+        assert_eq!(index.slots[&1], slot);
+        assert_eq!(cell_handles(&index, (0, 0)), vec![1, 2]);
+        assert_eq!(index.get_z_index(1), Some(3.0));
+        assert_eq!(index.query_point(21.0, 15.0), vec![1]);
+
+        // Crossing into the next cell moves cell membership
+        index.upsert(1, 300.0, 10.0, 310.0, 20.0, 3.0);
+        assert_eq!(index.slots[&1], slot);
+        assert_eq!(cell_handles(&index, (0, 0)), vec![2]);
+        assert_eq!(cell_handles(&index, (1, 0)), vec![1]);
+        assert!(index.query_point(15.0, 15.0).is_empty());
+    }
+
+    #[test]
+    fn test_estimated_memory_grows_with_nodes() {
+        let mut index = SpatialIndex::new(0);
+        let empty = index.estimated_memory_bytes();
+
+        for handle in 0..1000 {
+            let x = handle as f32 * 10.0;
+            index.upsert(handle, x, 0.0, x + 5.0, 5.0, 0.0);
+        }
+        let full = index.estimated_memory_bytes();
+        // At least the per-slot arrays: 4 bytes for each of 6 fields, plus 2 flags
+        assert!(full >= empty + 1000 * 26);
+
+        index.clear();
+        index.gc();
+        assert!(index.estimated_memory_bytes() < full);
     }
 }
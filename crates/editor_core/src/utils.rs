@@ -22,3 +22,14 @@ macro_rules! console_log {
 
 #[allow(unused_imports)]
 pub(crate) use console_log;
+
+/// Rough heap bytes of a hash map with the given capacity
+/// (one entry plus one control byte per bucket, as in hashbrown)
+pub(crate) fn map_bytes<K, V>(capacity: usize) -> usize {
+    capacity * (std::mem::size_of::<(K, V)>() + 1)
+}
+
+/// Heap bytes of a `Vec<T>` with the given capacity
+pub(crate) fn vec_bytes<T>(capacity: usize) -> usize {
+    capacity * std::mem::size_of::<T>()
+}
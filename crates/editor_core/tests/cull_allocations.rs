@@ -0,0 +1,69 @@
+//! Steady-state allocation check for the buffered cull path
+//!
+//! Lives in its own test binary so the counting allocator is not installed for other tests.
+
+use editor_core::EditorCore;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+/// Counts allocations made by the current thread (the test harness allocates on its own)
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn count_allocation() {
+    let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+}
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count_allocation();
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count_allocation();
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+#[test]
+fn cull_visible_buffered_does_not_allocate_in_steady_state() {
+    let mut core = EditorCore::new(1000);
+    for handle in 0..1000u32 {
+        let x = (handle % 40) as f32 * 60.0;
+        let y = (handle / 40) as f32 * 50.0;
+        core.upsert_node(handle, x, y, x + 40.0, y + 30.0, 0.0, handle % 7);
+    }
+    core.set_camera(1.0, 600.0, 500.0, 1280.0, 720.0, 1.0, None, None, None, None);
+
+    // First frame sizes the buffer and visit marks
+    let count = core.cull_visible_buffered();
+    assert!(count > 0);
+
+    let before = allocations();
+    for _ in 0..10 {
+        assert_eq!(core.cull_visible_buffered(), count);
+    }
+    assert_eq!(allocations(), before);
+
+    let handles = unsafe { std::slice::from_raw_parts(core.cull_buffer_ptr(), count) };
+    let mut expected = core.cull_visible();
+    let mut buffered = handles.to_vec();
+    expected.sort();
+    buffered.sort();
+    assert_eq!(buffered, expected);
+}
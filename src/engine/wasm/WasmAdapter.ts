@@ -82,12 +82,13 @@ export class WasmAdapter {
     id: NodeId,
     bounds: Bounds,
     zIndex: number,
-    flags: { hidden?: boolean; locked?: boolean }
+    flags: { hidden?: boolean; locked?: boolean; pinned?: boolean }
   ): void {
     if (!this.core) throw new Error('[WASM] Not initialized');
 
     const handle = this.idRegistry.getHandle(id);
-    const flagBits = (flags.hidden ? 0x1 : 0) | (flags.locked ? 0x2 : 0);
+    const flagBits =
+      (flags.hidden ? 0x1 : 0) | (flags.locked ? 0x2 : 0) | (flags.pinned ? 0x4 : 0);
 
     this.core.upsert_node(
       handle,